@@ -4,14 +4,16 @@ extern crate neon;
 extern crate lazy_static;
 extern crate fall_tree;
 extern crate lang_fall;
+extern crate lang_rust;
 
 use std::sync::Mutex;
 
 use neon::vm::{Call, JsResult};
 use neon::js::{JsString, JsArray, JsInteger, JsObject, JsNull, JsValue, Object};
 
-use lang_fall::{LANG_FALL, highlight};
-use fall_tree::{TextRange, TextUnit, File, dump_file};
+use lang_fall::{LANG_FALL, highlight, indenter};
+use fall_tree::{TextRange, TextUnit, File, LineCol, ColumnUnit, dump_file};
+use fall_tree::registry;
 use fall_tree::search::ancestors;
 
 mod util;
@@ -109,12 +111,33 @@ fn file_extend_selection(call: Call) -> JsResult<JsValue> {
 }
 
 
+fn file_indent_for_new_line(call: Call) -> JsResult<JsValue> {
+    let scope = call.scope;
+    let file = FILE.lock().unwrap();
+    let file = get_file_or_return_null!(file);
+    // The position comes from the editor, which measures columns in UTF-16 code units.
+    let line = call.arguments.require(scope, 0)?.check::<JsInteger>()?;
+    let col = call.arguments.require(scope, 1)?.check::<JsInteger>()?;
+    let index = file.line_index();
+    if line.value() < 0 || line.value() as usize >= index.line_count() || col.value() < 0 {
+        return Ok(JsNull::new().upcast());
+    }
+    let line_col = LineCol { line: line.value() as u32, col: col.value() as u32 };
+    let offset = index.offset(line_col, ColumnUnit::Utf16);
+    let indent = indenter().indent_for_new_line(&file, offset);
+    Ok(JsString::new(scope, &indent).unwrap().upcast())
+}
+
+
 
 register_module!(m, {
+    // Verbatim blocks are highlighted as Rust.
+    lang_rust::register(&mut registry::global());
     m.export("file_create", file_create)?;
     m.export("file_highlight", file_highlight)?;
     m.export("file_stats", file_stats)?;
     m.export("file_tree", file_tree)?;
     m.export("file_extend_selection", file_extend_selection)?;
+    m.export("file_indent_for_new_line", file_indent_for_new_line)?;
     Ok(())
 });
@@ -19,6 +19,7 @@ var backend = (() => {
         extendSelection: ([start, end]) => {
             return native.file_extend_selection(start, end);
         },
+        indentForNewLine: (line, character): string => native.file_indent_for_new_line(line, character),
         tree: (): string => native.file_tree()
     }
 })()
@@ -37,6 +38,7 @@ export function activate(context: vscode.ExtensionContext) {
         token: decor("#DFAF8F"),
         rule: decor("#93E0E3"),
         string: decor("#CC9393"),
+        literal: decor("#8CD0D3"),
         builtin: decor("#DD6718"),
         error: vscode.window.createTextEditorDecorationType({
             borderColor: "red",
@@ -127,7 +129,33 @@ export function activate(context: vscode.ExtensionContext) {
         let newSelection = new vscode.Selection(doc.positionAt(newStart), doc.positionAt(newEnd))
         activeEditor.selection = newSelection
     });
-    context.subscriptions.push(showSyntaxTree, semanticSelection, registration)
+    var onEnter = vscode.commands.registerCommand('extension.onEnter', async () => {
+        let editor = activeEditor
+        if (!editor) {
+            await vscode.commands.executeCommand('default:type', { text: '\n' })
+            return
+        }
+        let doc = editor.document
+        // Indents are computed for the text before the edit, as are the positions of all the selections.
+        let breaks = editor.selections.map(selection => {
+            let indent = backend.indentForNewLine(selection.start.line, selection.start.character)
+            // The rest of the line is reindented, so its old indentation goes away.
+            let rest = doc.lineAt(selection.end.line).text.substring(selection.end.character)
+            let end = selection.end.translate(0, rest.length - rest.replace(/^[ \t]+/, '').length)
+            return { range: new vscode.Range(selection.start, end), indent }
+        })
+        if (breaks.some(b => b.indent == null)) {
+            await vscode.commands.executeCommand('default:type', { text: '\n' })
+            return
+        }
+        await editor.edit(builder => {
+            for (let b of breaks) {
+                builder.replace(b.range, '\n' + b.indent)
+            }
+        })
+        editor.revealRange(editor.selection)
+    });
+    context.subscriptions.push(showSyntaxTree, semanticSelection, onEnter, registration)
     highlight()
 }
 
@@ -1,8 +1,10 @@
+use std::collections::HashMap;
+
 use serde_json;
 use fall_parse;
-use fall_tree::{Text, AstNode, AstClass};
-use lang_fall::{SelectorKind, RefKind, SynRule, Expr, FallFile, BlockExpr, };
-use util::{scream, camel};
+use fall_tree::{Text, AstNode, AstClass, Preorder, WalkEvent, NodeType, ERROR, WHITESPACE, language_tag};
+use lang_fall::{SelectorKind, RefKind, SynRule, Expr, FallFile, BlockExpr, CallExpr, CALL_EXPR};
+use util::{scream, camel, fnv_hash};
 use tera::{Tera, Context};
 
 pub type Result<T> = ::std::result::Result<T, Error>;
@@ -30,7 +32,13 @@ macro_rules! error {
 
 pub fn generate(file: FallFile) -> Result<String> {
     #[derive(Serialize)]
-    struct CtxLexRule<'f> { ty: Text<'f>, re: String, f: Option<Text<'f>> };
+    struct CtxLexRule<'f> { ty: Text<'f>, re: String, f: Option<Text<'f>>, trivia: bool };
+
+    #[derive(Serialize)]
+    struct CtxNodeType { name: String, token: bool, keyword: bool, literal: bool, text: Option<String>, classes: Vec<String> }
+
+    #[derive(Serialize)]
+    struct CtxInjection { parent: String, token: String, language: String }
 
     #[derive(Serialize)]
     struct CtxAstNode<'f> { struct_name: String, node_type_name: String, methods: Vec<CtxMethod<'f>> }
@@ -42,12 +50,50 @@ pub fn generate(file: FallFile) -> Result<String> {
     struct CtxMethod<'f> { name: Text<'f>, ret_type: String, body: String }
 
     let mut context = Context::new();
+    let language = file.language_def()
+        .ok_or(error!("no language name defined"))?;
+    context.add("language_name", &language.name());
+    context.add("language_extensions", &language.extensions());
+    context.add("language_version", &fnv_hash(&file.node().text().to_cow()));
+    context.add("language_tag", &language_tag(&language.name().to_cow()));
     context.add("node_types", &file.node_types());
 
+    // `#[memo]` on the language definition memoizes all rules.
+    let memo_all = language.attributes().is_some_and(|attrs| attrs.is_memo());
+    let n_rules = file.syn_rules().count();
+    let keywords = contextual_keywords(file)?;
+    let mut instances = Instances { n_rules, rules: Vec::new(), depth: 0, keywords };
     let mut parser = Vec::new();
     for r in file.syn_rules() {
-        if let Some(r) = compile_rule(r)? {
-            parser.push(r)
+        parser.push(if r.parameters().next().is_some() {
+            // Only the instances of parameterized rules are called.
+            fall_parse::SynRule { body: fall_parse::Expr::Or(Vec::new()), memo: false, left_recursive: false }
+        } else {
+            compile_rule(r, &[], &mut instances)?
+        });
+    }
+    // Instances may call further instances, so the list grows while it is compiled.
+    while parser.len() < n_rules + instances.rules.len() {
+        let Instance { rule, ref args, depth } = instances.rules[parser.len() - n_rules];
+        let args = args.clone();
+        instances.depth = depth;
+        parser.push(compile_rule(rule, &args, &mut instances)?);
+    }
+    for r in parser.iter_mut() {
+        r.memo |= memo_all;
+    }
+    for rule in 0..parser.len() {
+        match fall_parse::left_recursion(&parser, rule) {
+            // Direct left recursion is handled by the parser.
+            Some(ref path) if path.len() == 2 => parser[rule].left_recursive = true,
+            Some(path) => {
+                let names = path.iter()
+                    .map(|&idx| if idx < n_rules { file.syn_rules().nth(idx) } else { Some(instances.rules[idx - n_rules].rule) })
+                    .map(|r| r.and_then(|r| r.name()).map(|name| name.to_string()).unwrap_or_default())
+                    .collect::<Vec<_>>();
+                return Err(error!("indirect left recursion: {}", names.join(" -> ")));
+            }
+            None => {}
         }
     }
     let parser = serde_json::to_string(&parser).unwrap();
@@ -56,12 +102,61 @@ pub fn generate(file: FallFile) -> Result<String> {
     let lex_rules = file.tokenizer_def()
         .ok_or(error!("no tokens defined"))?
         .lex_rules()
+        .filter(|r| !r.is_contextual())
         .map(|r| {
             let re = r.token_re().ok_or(error!("Bad token"))?;
-            Ok(CtxLexRule { ty: r.node_type(), re: format!("{:?}", re), f: r.extern_fn() })
+            Ok(CtxLexRule { ty: r.node_type(), re: format!("{:?}", re), f: r.extern_fn(), trivia: r.is_trivia() })
         }).collect::<Result<Vec<_>>>()?;
 
     context.add("lex_rules", &lex_rules);
+
+    let node_types = file.node_types().into_iter().map(|name| {
+        let lex_rule = file.tokenizer_def().unwrap().lex_rules().find(|r| r.node_type() == name);
+        let text = lex_rule.and_then(|r| r.fixed_text());
+        let classes = file.ast_def().into_iter()
+            .flat_map(|ast| ast.ast_classes())
+            .filter(|class| class.variants().any(|variant| variant == name))
+            .map(|class| format!("{:?}", class.name().to_cow()))
+            .collect();
+        CtxNodeType {
+            name: scream(name),
+            token: lex_rule.is_some(),
+            keyword: text.is_some_and(|text| is_word(&text.to_cow())),
+            literal: lex_rule.is_some_and(|r| r.is_literal()),
+            text: text.map(|text| format!("{:?}", text.to_cow())),
+            classes,
+        }
+    }).collect::<Vec<_>>();
+    context.add("node_type_infos", &node_types);
+
+    let mut injections = Vec::new();
+    for rule in file.syn_rules() {
+        for event in Preorder::new(rule.node()) {
+            let call = match event {
+                WalkEvent::Enter(node) if node.ty() == CALL_EXPR => CallExpr::new(node),
+                _ => continue,
+            };
+            if call.fn_name() != "inject" {
+                continue;
+            }
+            let parent = match rule.name() {
+                Some(name) if rule.is_pub() => name,
+                _ => return Err(error!("inject is allowed only in public rules")),
+            };
+            let mut args = call.args();
+            let language = args.next().ok_or(error!("expected a language name"))?;
+            let token = match args.next().and_then(|arg| arg.token_set()) {
+                Some(ref tokens) if tokens.len() == 1 => file.node_types()[tokens[0] - 2],
+                _ => return Err(error!("expected a token to inject into: `{}`", call.node().text())),
+            };
+            injections.push(CtxInjection {
+                parent: scream(parent),
+                token: scream(token),
+                language: language.node().text().to_string(),
+            });
+        }
+    }
+    context.add("injections", &injections);
     context.add("verbatim", &file.verbatim_def().map(|v| v.contents()));
 
     if let Some(ast) = file.ast_def() {
@@ -104,15 +199,60 @@ pub fn generate(file: FallFile) -> Result<String> {
         .map_err(|_| error!("Failed to format template"))
 }
 
-fn compile_rule(ast: SynRule) -> Result<Option<fall_parse::SynRule>> {
+fn is_word(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_alphabetic()) && text.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Instances of the parameterized rules, one for each distinct list of arguments.
+/// They are appended to the parser after the rules of the file.
+struct Instances<'f> {
+    n_rules: usize,
+    rules: Vec<Instance<'f>>,
+    /// The depth of the rule being compiled.
+    depth: usize,
+    /// Compiled contextual keywords, by their node types.
+    keywords: HashMap<usize, fall_parse::Expr>,
+}
+
+struct Instance<'f> {
+    rule: SynRule<'f>,
+    args: Vec<fall_parse::Expr>,
+    /// The rules of the file call instances of depth one, which call instances of depth two and so on.
+    depth: usize,
+}
+
+impl<'f> Instances<'f> {
+    /// Arguments, which grow with each call, like in `rule r(x) { <r {x x}> }`, yield infinitely many instances.
+    const MAX_DEPTH: usize = 16;
+
+    /// The index of the parser rule, which is the `rule` with the `args` substituted for its parameters.
+    fn instantiate(&mut self, rule: SynRule<'f>, args: Vec<fall_parse::Expr>) -> Result<usize> {
+        let idx = match self.rules.iter().position(|i| i.rule.node() == rule.node() && i.args == args) {
+            Some(idx) => idx,
+            None if self.depth == Self::MAX_DEPTH => {
+                return Err(error!("too many instances of `{}`", rule.name().map(|name| name.to_string()).unwrap_or_default()));
+            }
+            None => {
+                self.rules.push(Instance { rule, args, depth: self.depth + 1 });
+                self.rules.len() - 1
+            }
+        };
+        Ok(self.n_rules + idx)
+    }
+}
+
+fn compile_rule<'f>(ast: SynRule<'f>, args: &[fall_parse::Expr], instances: &mut Instances<'f>) -> Result<fall_parse::SynRule> {
     let expr = match ast.attributes() {
         Some(attrs) if attrs.is_pratt() => {
+            if ast.parameters().next().is_some() {
+                return Err(error!("pratt rule with parameters: `{}`", ast.name().map(|name| name.to_string()).unwrap_or_default()));
+            }
             match ast.body() {
-                Expr::BlockExpr(block) => fall_parse::Expr::Pratt(compile_pratt(block)?),
+                Expr::BlockExpr(block) => fall_parse::Expr::Pratt(compile_pratt(block, instances)?),
                 _ => unreachable!()
             }
         }
-        _ => compile_expr(ast.body())?
+        _ => compile_expr(ast.body(), args, instances)?
     };
     let expr = if let Some(idx) = ast.resolve_ty() {
         fall_parse::Expr::Pub(idx, Box::new(expr))
@@ -120,14 +260,17 @@ fn compile_rule(ast: SynRule) -> Result<Option<fall_parse::SynRule>> {
         expr
     };
 
-    Ok(Some(fall_parse::SynRule { body: expr }))
+    let memo = ast.attributes().is_some_and(|attrs| attrs.is_memo());
+    Ok(fall_parse::SynRule { body: expr, memo, left_recursive: false })
 }
 
-fn compile_pratt(ast: BlockExpr) -> Result<Vec<fall_parse::PrattVariant>> {
+fn compile_pratt<'f>(ast: BlockExpr<'f>, instances: &mut Instances<'f>) -> Result<Vec<fall_parse::PrattVariant>> {
     fn alt_to_rule<'f>(alt: Expr<'f>) -> Result<SynRule<'f>> {
         match alt {
             Expr::SeqExpr(expr) => match expr.parts().next() {
                 Some(Expr::RefExpr(r)) => match r.resolve() {
+                    Some(RefKind::RuleReference(rule)) if rule.parameters().next().is_some() =>
+                        Err(error!("pratt rule with parameters: `{}`", r.node().text())),
                     Some(RefKind::RuleReference(rule)) => Ok(rule),
                     _ => return Err(error!("Bad pratt spec")),
                 },
@@ -144,26 +287,52 @@ fn compile_pratt(ast: BlockExpr) -> Result<Vec<fall_parse::PrattVariant>> {
         let attrs = rule.attributes().ok_or(error!("pratt rule without attributes"))?;
         if attrs.is_atom() {
             result.push(fall_parse::PrattVariant::Atom {
-                body: Box::new(compile_rule(rule)?.unwrap().body),
+                body: Box::new(compile_rule(rule, &[], instances)?.body),
             })
         }
 
-        if let Some(priority) = attrs.bin_priority() {
+        let parts = || -> Result<Vec<Expr>> {
             let alt = match rule.body() {
                 Expr::BlockExpr(block) => block.alts().next().ok_or(error!(
                 "bad pratt rule"
                 ))?,
                 _ => return Err(error!("bad pratt rule"))
             };
-            let op = match alt {
-                Expr::SeqExpr(seq) => seq.parts().nth(1).unwrap(),
-                _ => return Err(error!("bad pratt rule"))
-            };
+            match alt {
+                Expr::SeqExpr(seq) if seq.parts().count() >= 2 => Ok(seq.parts().collect()),
+                _ => Err(error!("bad pratt rule"))
+            }
+        };
+        // Operators are the parts of the rule, except for the operands.
+        fn compile_op<'f>(parts: &[Expr<'f>], instances: &mut Instances<'f>) -> Result<Box<fall_parse::Expr>> {
+            let parts = parts.iter().map(|&p| compile_expr(p, &[], instances)).collect::<Result<Vec<_>>>()?;
+            Ok(Box::new(fall_parse::Expr::And(parts, None)))
+        }
 
+        if let Some(priority) = attrs.bin_priority() {
+            let op = parts()?[1];
             result.push(fall_parse::PrattVariant::Binary {
                 ty: ty,
-                op: Box::new(compile_expr(op)?),
-                priority: priority
+                op: Box::new(compile_expr(op, &[], instances)?),
+                priority: priority,
+                right: attrs.is_right(),
+            })
+        }
+
+        if let Some(priority) = attrs.prefix_priority() {
+            let parts = parts()?;
+            result.push(fall_parse::PrattVariant::Prefix {
+                ty,
+                op: compile_op(&parts[..parts.len() - 1], instances)?,
+                priority,
+            })
+        }
+
+        if let Some(priority) = attrs.postfix_priority() {
+            result.push(fall_parse::PrattVariant::Postfix {
+                ty,
+                op: compile_op(&parts()?[1..], instances)?,
+                priority,
             })
         }
     }
@@ -171,9 +340,12 @@ fn compile_pratt(ast: BlockExpr) -> Result<Vec<fall_parse::PrattVariant>> {
     Ok(result)
 }
 
-fn compile_expr(ast: Expr) -> Result<fall_parse::Expr> {
+/// Compiles the expression of a rule, `args` are substituted for the parameters of the rule.
+fn compile_expr<'f>(ast: Expr<'f>, args: &[fall_parse::Expr], instances: &mut Instances<'f>) -> Result<fall_parse::Expr> {
     let result = match ast {
-        Expr::BlockExpr(block) => fall_parse::Expr::Or(block.alts().map(compile_expr).collect::<Result<Vec<_>>>()?),
+        Expr::BlockExpr(block) => fall_parse::Expr::Or(
+            block.alts().map(|alt| compile_expr(alt, args, instances)).collect::<Result<Vec<_>>>()?
+        ),
         Expr::SeqExpr(seq) => {
             fn is_commit(part: Expr) -> bool {
                 part.node().text() == "<commit>"
@@ -181,12 +353,18 @@ fn compile_expr(ast: Expr) -> Result<fall_parse::Expr> {
             let commit = seq.parts().position(is_commit);
             let parts = seq.parts()
                 .filter(|&p| !is_commit(p))
-                .map(compile_expr);
+                .map(|p| compile_expr(p, args, instances));
             fall_parse::Expr::And(parts.collect::<Result<Vec<_>>>()?, commit)
         }
         Expr::RefExpr(ref_) => match ref_.resolve() {
-            Some(RefKind::Token(idx)) => fall_parse::Expr::Token(idx),
+            Some(RefKind::Token(idx, rule)) if rule.is_contextual() => instances.keywords[&idx].clone(),
+            Some(RefKind::Token(idx, _)) => fall_parse::Expr::Token(idx),
+            Some(RefKind::RuleReference(rule)) if rule.parameters().next().is_some() =>
+                return Err(error!("Rule with parameters should be called as `<{} ...>`", ref_.node().text())),
             Some(RefKind::RuleReference(rule)) => fall_parse::Expr::Rule(rule.index()),
+            Some(RefKind::Param(idx)) => args.get(idx).cloned().ok_or(
+                error!("Unbound parameter: {}", ref_.node().text())
+            )?,
             None => return Err(error!("Unresolved references: {}", ref_.node().text())),
         },
         Expr::CallExpr(call) => {
@@ -194,44 +372,67 @@ fn compile_expr(ast: Expr) -> Result<fall_parse::Expr> {
             if fn_name == "eof" {
                 return Ok(fall_parse::Expr::Eof)
             }
-            let mut args = call.args();
-            let first_arg = args.next().ok_or(error!("expected an argument"))?;
-            macro_rules! token_set_arg {
-                () => {
-                    first_arg.token_set().ok_or(
+            let mut call_args = call.args();
+            let first_arg = call_args.next().ok_or(error!("expected an argument"))?;
+            match fn_name.as_ref() {
+                "not" => fall_parse::Expr::Not(
+                    token_set(&compile_expr(first_arg, args, instances)?).ok_or(
                         error!("Bad token set: `{}`", first_arg.node().text())
                     )?
-                }
-            }
-            match fn_name.as_ref() {
-                "not" => fall_parse::Expr::Not(token_set_arg!()),
+                ),
                 "rep" => {
-                    if args.next().is_some() {
+                    if call_args.next().is_some() {
                         return Err(error!("extra argument to rep"))
                     }
-                    fall_parse::Expr::Rep(Box::new(compile_expr(first_arg)?))
+                    fall_parse::Expr::Rep(Box::new(compile_expr(first_arg, args, instances)?))
                 }
                 "not_ahead" => {
-                    if args.next().is_some() {
+                    if call_args.next().is_some() {
                         return Err(error!("extra argument to not_ahead"))
                     }
-                    fall_parse::Expr::NotAhead(Box::new(compile_expr(first_arg)?))
+                    fall_parse::Expr::NotAhead(Box::new(compile_expr(first_arg, args, instances)?))
                 }
 
-                "opt" => fall_parse::Expr::Opt(Box::new(compile_expr(first_arg)?)),
+                "opt" => fall_parse::Expr::Opt(Box::new(compile_expr(first_arg, args, instances)?)),
                 "layer" => fall_parse::Expr::Layer(
-                    Box::new(compile_expr(first_arg)?),
-                    Box::new(compile_expr(args.next().ok_or(
+                    Box::new(compile_expr(first_arg, args, instances)?),
+                    Box::new(compile_expr(call_args.next().ok_or(
                         error!("not enough arguments to layer")
-                    )?)?)
+                    )?, args, instances)?)
                 ),
+                "inject" => compile_expr(call_args.next().ok_or(
+                    error!("not enough arguments to inject")
+                )?, args, instances)?,
                 "with_skip" => fall_parse::Expr::WithSkip(
-                    Box::new(compile_expr(first_arg)?),
-                    Box::new(compile_expr(args.next().ok_or((
+                    Box::new(compile_expr(first_arg, args, instances)?),
+                    Box::new(compile_expr(call_args.next().ok_or((
                         error!("not enough arguments to layer")
-                    ))?)?)
+                    ))?, args, instances)?)
                 ),
-                _ => unimplemented!(),
+                "sep_by" => fall_parse::Expr::SepBy(
+                    Box::new(compile_expr(first_arg, args, instances)?),
+                    Box::new(compile_expr(call_args.next().ok_or(
+                        error!("not enough arguments to sep_by")
+                    )?, args, instances)?),
+                    match call_args.next() {
+                        None => fall_parse::Trailing::Forbid,
+                        Some(option) if option.node().text() == "trailing" => fall_parse::Trailing::Allow,
+                        Some(option) if option.node().text() == "report_trailing" => fall_parse::Trailing::Report,
+                        Some(option) => return Err(error!("unknown option of sep_by: `{}`", option.node().text())),
+                    }
+                ),
+                _ => match call.resolve_rule() {
+                    Some(rule) => {
+                        let call_args = call.args()
+                            .map(|arg| compile_expr(arg, args, instances))
+                            .collect::<Result<Vec<_>>>()?;
+                        if call_args.len() != rule.parameters().count() {
+                            return Err(error!("wrong number of arguments: `{}`", call.node().text()))
+                        }
+                        fall_parse::Expr::Rule(instances.instantiate(rule, call_args)?)
+                    }
+                    None => return Err(error!("unknown function: `{}`", fn_name)),
+                },
             }
         }
     };
@@ -239,18 +440,66 @@ fn compile_expr(ast: Expr) -> Result<fall_parse::Expr> {
     Ok(result)
 }
 
+/// Contextual keywords are matched by the text of the token, which the lexer produces for them.
+/// Rules with external functions can't be run by the generator, so they don't produce such tokens.
+fn contextual_keywords(file: FallFile) -> Result<HashMap<usize, fall_parse::Expr>> {
+    let tokenizer = file.tokenizer_def().ok_or(error!("no tokens defined"))?;
+    let lexer = tokenizer.lex_rules()
+        .filter(|r| !r.is_contextual() && r.extern_fn().is_none())
+        .map(|r| {
+            let re = r.token_re().ok_or(error!("Bad token"))?;
+            let ty = file.resolve_ty(r.node_type()).map_or(WHITESPACE, |idx| NodeType(idx as u32));
+            Ok(fall_parse::LexRule::new(ty, &re, None))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut result = HashMap::new();
+    for rule in tokenizer.lex_rules().filter(|r| r.is_contextual()) {
+        let name = rule.node_type();
+        let text = rule.fixed_text()
+            .ok_or(error!("contextual keyword `{}` should be a string", name))?
+            .to_string();
+        let tokens = fall_parse::tokenize(&text, &lexer).collect::<Vec<_>>();
+        let lexed_as = match tokens.as_slice() {
+            [token] if token.ty != ERROR && token.ty != WHITESPACE => token.ty.0 as usize,
+            _ => return Err(error!("no token matches contextual keyword `{}`", name)),
+        };
+        let idx = file.resolve_ty(name).unwrap();
+        result.insert(idx, fall_parse::Expr::ContextualKeyword(lexed_as, text, idx));
+    }
+    Ok(result)
+}
+
+/// Tokens of an expression, which matches a single token from a set, like `{'(' | '['}`.
+fn token_set(expr: &fall_parse::Expr) -> Option<Vec<usize>> {
+    match *expr {
+        fall_parse::Expr::Token(ty) => Some(vec![ty]),
+        fall_parse::Expr::And(ref parts, None) if parts.len() <= 1 =>
+            parts.first().map_or(Some(Vec::new()), token_set),
+        fall_parse::Expr::Or(ref alts) => {
+            let mut result = Vec::new();
+            for alt in alts {
+                result.extend(token_set(alt)?)
+            }
+            Some(result)
+        }
+        _ => None,
+    }
+}
+
 const TEMPLATE: &'static str = r#####"
 use serde_json;
-use fall_tree::{NodeType, NodeTypeInfo, Language, LanguageImpl, FileStats, INode};
+use fall_tree::{NodeType, NodeTypeInfo, Language, LanguageImpl, Injection, FileStats, INode, File, Edit};
+use fall_tree::registry::Registry;
 pub use fall_tree::{ERROR, WHITESPACE};
 
 {% for node_type in node_types %}
-pub const {{ node_type | upper }}: NodeType = NodeType({{ 100 + loop.index0 }});
+pub const {{ node_type | upper }}: NodeType = NodeType(({{ language_tag }} << 16) | {{ 100 + loop.index0 }});
 {% endfor %}
 
 lazy_static! {
     pub static ref LANG: Language = {
-        use fall_parse::{LexRule, SynRule, Parser};
+        use fall_parse::{LexRule, Trivia, SynRule, Parser};
         const ALL_NODE_TYPES: &[NodeType] = &[
             ERROR, WHITESPACE,
             {% for node_type in node_types %}{{ node_type | upper }}, {% endfor %}
@@ -258,36 +507,99 @@ lazy_static! {
         let parser_json = r##"{{ parser_json }}"##;
         let parser: Vec<SynRule> = serde_json::from_str(parser_json).unwrap();
 
-        struct Impl { tokenizer: Vec<LexRule>, parser: Vec<SynRule> };
+        struct Impl { tokenizer: Vec<LexRule>, trivia: Trivia, parser: Vec<SynRule> };
         impl LanguageImpl for Impl {
+            fn name(&self) -> &'static str {
+                "{{ language_name }}"
+            }
+
+            fn extensions(&self) -> &'static [&'static str] {
+                &[{% for ext in language_extensions %}"{{ ext }}"{% if not loop.last %}, {% endif %}{% endfor %}]
+            }
+
+            fn version(&self) -> u64 {
+                {{ language_version }}
+            }
+
             fn parse(&self, text: &str) -> (FileStats, INode) {
-                ::fall_parse::parse(text, &self.tokenizer, &|tokens, stats| {
+                ::fall_parse::parse(text, &self.tokenizer, &self.trivia, &|tokens, stats| {
                     Parser::new(ALL_NODE_TYPES, &self.parser).parse(tokens, stats)
                 })
             }
 
+            fn reparse(&self, file: &File, edit: &Edit, new_text: &str) -> (FileStats, INode) {
+                let parser = Parser::new(ALL_NODE_TYPES, &self.parser);
+                ::fall_parse::reparse(file, edit, new_text, &self.tokenizer, &self.trivia, &parser)
+            }
+
+            fn is_trivia(&self, ty: NodeType) -> bool {
+                self.trivia.contains(ty)
+            }
+
+            fn injections(&self) -> &'static [Injection] {
+                &[
+                    {% for injection in injections %}
+                    Injection { parent: {{ injection.parent }}, token: {{ injection.token }}, language: "{{ injection.language }}" },
+                    {% endfor %}
+                ]
+            }
+
+            fn node_types(&self) -> &'static [NodeType] {
+                ALL_NODE_TYPES
+            }
+
             fn node_type_info(&self, ty: NodeType) -> NodeTypeInfo {
                 match ty {
-                    ERROR => NodeTypeInfo { name: "ERROR" },
-                    WHITESPACE => NodeTypeInfo { name: "WHITESPACE" },
-                    {% for node_type in node_types %}
-                    {{ node_type | upper }} => NodeTypeInfo { name: "{{ node_type | upper }}" },
+                    ERROR => NodeTypeInfo {
+                        name: "ERROR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    WHITESPACE => NodeTypeInfo {
+                        name: "WHITESPACE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    {% for info in node_type_infos %}
+                    {{ info.name }} => NodeTypeInfo {
+                        name: "{{ info.name }}",
+                        is_token: {{ info.token }},
+                        is_keyword: {{ info.keyword }},
+                        is_literal: {{ info.literal }},
+                        text: {% if info.text %}Some({{ info.text }}){% else %}None{% endif %},
+                        classes: &[{% for class in info.classes %}{{ class }}{% if not loop.last %}, {% endif %}{% endfor %}],
+                    },
                     {% endfor %}
                     _ => panic!("Unknown NodeType: {:?}", ty)
                 }
             }
         }
 
+        let tokenizer = vec![
+            {% for rule in lex_rules %}
+            LexRule::{% if rule.trivia %}new_trivia{% else %}new{% endif %}({{ rule.ty | upper }}, {{ rule.re }}, {% if rule.f is string %} Some({{ rule.f }}) {% else %} None {% endif %}),
+            {% endfor %}
+        ];
         Language::new(Impl {
-            tokenizer: vec![
-                {% for rule in lex_rules %}
-                LexRule::new({{ rule.ty | upper }}, {{ rule.re }}, {% if rule.f is string %} Some({{ rule.f }}) {% else %} None {% endif %}),
-                {% endfor %}
-            ],
+            trivia: Trivia::new(&tokenizer),
+            tokenizer,
             parser: parser,
         })
     };
 }
+
+/// Adds the language to the `registry`.
+/// Use `register(&mut fall_tree::registry::global())` to make it available process-wide.
+pub fn register(registry: &mut Registry) {
+    registry.register(LANG.clone())
+}
+
 {% if verbatim is string %}
 {{ verbatim }}
 {% endif %}
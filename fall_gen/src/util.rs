@@ -11,3 +11,10 @@ pub fn camel(word: Text) -> String {
         .map(|w| w[..1].to_ascii_uppercase() + &w[1..])
         .collect()
 }
+
+/// FNV-1a hash, which, unlike `DefaultHasher`, is guaranteed to be stable.
+pub fn fnv_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
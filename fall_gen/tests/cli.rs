@@ -67,3 +67,78 @@ fn test_grammars_are_fresh() {
 
     check_by_path("../lang/fall/src/syntax.fall")
 }
+
+/// Runs the generator on a bad grammar and returns the error message.
+fn generator_error(grammar_text: &str) -> String {
+    let dir = TempDir::new("gen-tests").unwrap();
+    let grammar = dir.path().join("grammar.fall");
+    file::put_text(&grammar, grammar_text).unwrap();
+
+    let output = process::Command::new(generator_path())
+        .arg(&grammar)
+        .output()
+        .expect("Failed to execute process");
+    assert!(!output.status.success());
+    std::str::from_utf8(&output.stderr).unwrap().to_owned()
+}
+
+#[test]
+fn reports_indirect_left_recursion() {
+    let stderr = generator_error("\
+language foo
+tokenizer { x 'x' y 'y' }
+pub rule file { a }
+rule a { <opt 'y'> b 'x' | 'x' }
+rule b { c }
+rule c { a 'y' }
+");
+    assert!(stderr.contains("indirect left recursion: a -> b -> c -> a"), "{}", stderr);
+}
+
+#[test]
+fn reports_bad_calls_of_parameterized_rules() {
+    let stderr = generator_error("\
+language foo
+tokenizer { x 'x' y 'y' }
+pub rule file { <pair 'x'> }
+rule pair(first, second) { first second }
+");
+    assert!(stderr.contains("wrong number of arguments: `<pair 'x'>`"), "{}", stderr);
+
+    let stderr = generator_error("\
+language foo
+tokenizer { x 'x' y 'y' }
+pub rule file { <nested 'x'> }
+rule nested(a) { 'y' <nested {a a}> | a }
+");
+    assert!(stderr.contains("too many instances of `nested`"), "{}", stderr);
+
+    let stderr = generator_error("\
+language foo
+tokenizer { x 'x' y 'y' }
+pub rule file { expr }
+#[pratt] rule expr { sum_expr | atom }
+#[bin(1)] pub rule sum_expr(op) { expr op expr }
+#[atom] pub rule atom { 'x' }
+");
+    assert!(stderr.contains("pratt rule with parameters: `sum_expr`"), "{}", stderr);
+
+    let stderr = generator_error("\
+language foo
+tokenizer { x 'x' y 'y' }
+pub rule file { <expr 'x'> }
+#[pratt] rule expr(a) { atom }
+#[atom] pub rule atom { 'x' }
+");
+    assert!(stderr.contains("pratt rule with parameters: `expr`"), "{}", stderr);
+}
+
+#[test]
+fn reports_contextual_keywords_without_token() {
+    let stderr = generator_error("\
+language foo
+tokenizer { x 'x' #[contextual] kw 'kw' }
+pub rule file { 'kw' }
+");
+    assert!(stderr.contains("no token matches contextual keyword `kw`"), "{}", stderr);
+}
@@ -1,5 +1,5 @@
 use regex::Regex;
-use fall_tree::{NodeType, ERROR, TextUnit};
+use fall_tree::{NodeType, ERROR, WHITESPACE, TextUnit, Edit};
 
 pub type CustomRule = fn(&str) -> Option<usize>;
 
@@ -7,6 +7,7 @@ pub struct LexRule {
     pub ty: NodeType,
     pub re: Regex,
     pub f: Option<CustomRule>,
+    pub trivia: bool,
 }
 
 impl LexRule {
@@ -15,8 +16,37 @@ impl LexRule {
             ty: ty,
             re: Regex::new(&format!("^({})", re)).unwrap(),
             f: f,
+            trivia: false,
         }
     }
+
+    /// A rule for tokens which are skipped by the parser, like comments.
+    pub fn new_trivia(ty: NodeType, re: &str, f: Option<CustomRule>) -> LexRule {
+        LexRule { trivia: true, ..LexRule::new(ty, re, f) }
+    }
+}
+
+/// Types of the trivia tokens, computed once per tokenizer, because they are checked for every token.
+///
+/// Whitespace is always trivia, other tokens are trivia if they are declared so in the tokenizer.
+#[derive(Debug, Clone)]
+pub struct Trivia {
+    /// Sorted by the raw value of the type.
+    types: Vec<NodeType>,
+}
+
+impl Trivia {
+    pub fn new(rules: &[LexRule]) -> Trivia {
+        let mut types: Vec<NodeType> = rules.iter().filter(|rule| rule.trivia).map(|rule| rule.ty).collect();
+        types.push(WHITESPACE);
+        types.sort_by_key(|ty| ty.0);
+        types.dedup();
+        Trivia { types }
+    }
+
+    pub fn contains(&self, ty: NodeType) -> bool {
+        self.types.binary_search_by_key(&ty.0, |t| t.0).is_ok()
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -74,6 +104,67 @@ impl<'t, 'r> Iterator for TokenIter<'t, 'r> {
     }
 }
 
+/// Lexes `new_text`, which is the result of applying `edit` to the text of `old_tokens`.
+///
+/// Lexing stops as soon as the new tokens line up with the old ones, and the rest of the old tokens is reused.
+/// It starts from the token before the damaged region, or earlier, from the start of the damaged line,
+/// because a token may grow up to the damage. A bad char may become a part of a token after it, like an unclosed
+/// quote, so lexing starts from the first bad char before the damage, if any.
+pub fn relex(old_tokens: &[Token], edit: &Edit, new_text: &str, rules: &[LexRule]) -> Vec<Token> {
+    let mut result = Vec::new();
+    let damage_start = edit.delete.start();
+    let line_start = new_text[..damage_start.as_u32() as usize].rfind('\n').map_or(0, |idx| idx + 1);
+    let mut restart = TextUnit::from_usize(line_start);
+    let mut offset = TextUnit::zero();
+    for token in old_tokens {
+        if offset >= restart {
+            break;
+        }
+        if token.ty == ERROR {
+            restart = offset;
+            break;
+        }
+        offset += token.len;
+    }
+
+    let mut old_offset = TextUnit::zero();
+    let mut old_idx = 0;
+    while old_idx < old_tokens.len() && old_offset + old_tokens[old_idx].len < damage_start {
+        old_offset += old_tokens[old_idx].len;
+        old_idx += 1;
+    }
+    if old_idx > 0 {
+        old_idx -= 1;
+        old_offset = old_offset - old_tokens[old_idx].len;
+    }
+    while old_idx > 0 && old_offset > restart {
+        old_idx -= 1;
+        old_offset = old_offset - old_tokens[old_idx].len;
+    }
+    result.extend_from_slice(&old_tokens[..old_idx]);
+
+    let insert_len = TextUnit::measure(&edit.insert);
+    let damage_end = edit.delete.start() + insert_len;
+    let mut new_offset = old_offset;
+    for token in tokenize(&new_text[old_offset.as_u32() as usize..], rules) {
+        new_offset += token.len;
+        result.push(token);
+        if new_offset < damage_end {
+            continue;
+        }
+        let old_pos = new_offset + edit.delete.len() - insert_len;
+        while old_idx < old_tokens.len() && old_offset < old_pos {
+            old_offset += old_tokens[old_idx].len;
+            old_idx += 1;
+        }
+        if old_offset == old_pos {
+            result.extend_from_slice(&old_tokens[old_idx..]);
+            break;
+        }
+    }
+    result
+}
+
 impl<'t, 'r> TokenIter<'t, 'r> {
     fn bad_char(&mut self) -> Token {
         let char_len = self.rest.chars().next().unwrap().len_utf8();
@@ -101,3 +192,55 @@ fn tokenize_longest_first_wins() {
         .collect();
     assert_eq!(tokens, vec![10, 1, 11, 1, 11]);
 }
+
+#[test]
+fn trivia_includes_whitespace() {
+    let rules = &[
+        LexRule::new(NodeType(12), "x", None),
+        LexRule::new_trivia(NodeType(11), "#.*", None),
+        LexRule::new_trivia(NodeType(10), "//.*", None),
+    ];
+    let trivia = Trivia::new(rules);
+    assert!(trivia.contains(WHITESPACE));
+    assert!(trivia.contains(NodeType(10)));
+    assert!(trivia.contains(NodeType(11)));
+    assert!(!trivia.contains(NodeType(12)));
+    assert!(!trivia.contains(ERROR));
+}
+
+#[test]
+fn relex_reuses_tokens_after_damage() {
+    use fall_tree::TextRange;
+
+    let rules = &[
+        LexRule::new(::fall_tree::WHITESPACE, r"\s+", None),
+        LexRule::new(NodeType(10), r"\w+", None),
+        LexRule::new(NodeType(11), r#""[^"]*""#, None),
+    ];
+
+    let check = |before: &str, delete: (u32, u32), insert: &str| {
+        let edit = Edit {
+            delete: TextRange::from_to(TextUnit::from_usize(delete.0 as usize), TextUnit::from_usize(delete.1 as usize)),
+            insert: insert.to_owned(),
+        };
+        let after = before[..delete.0 as usize].to_owned() + insert + &before[delete.1 as usize..];
+        let old_tokens: Vec<_> = tokenize(before, rules).collect();
+        let relexed: Vec<_> = relex(&old_tokens, &edit, &after, rules).iter()
+            .map(|t| (t.ty.0, t.len.as_u32()))
+            .collect();
+        let expected: Vec<_> = tokenize(&after, rules)
+            .map(|t| (t.ty.0, t.len.as_u32()))
+            .collect();
+        assert_eq!(relexed, expected, "{:?} -> {:?}", before, after);
+    };
+
+    check("foo bar baz", (4, 7), "quux");
+    check("foo bar baz", (3, 4), "");
+    check("foo bar baz", (11, 11), "z");
+    check("foo bar baz", (0, 0), " ");
+    check("foo bar \"baz\"", (4, 4), "\"");
+    check("foo \"bar\" baz", (4, 5), "");
+    check("[\"a b]", (5, 5), "\"");
+    check("\"a\nb\nc", (5, 5), "\"");
+    check("x\n\"a\nb c", (7, 7), "\"");
+}
@@ -7,7 +7,9 @@ extern crate fall_tree;
 mod lex;
 mod syn;
 mod tree_builder;
+mod reparse;
 
-pub use lex::{LexRule};
-pub use syn::{SynRule, Expr, Parser, PrattVariant};
-pub use tree_builder::{parse, reparse};
+pub use lex::{LexRule, Token, Trivia, tokenize};
+pub use syn::{SynRule, Expr, Parser, PrattVariant, Trailing, left_recursion};
+pub use tree_builder::parse;
+pub use reparse::reparse;
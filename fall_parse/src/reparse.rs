@@ -0,0 +1,223 @@
+use std::ops::Range;
+
+use elapsed::measure_time;
+
+use fall_tree::{File, Node, NodeType, Edit, FileStats, INode, TextRange, TextUnit};
+use lex::{Token, LexRule, Trivia, relex};
+use syn::Parser;
+use tree_builder::{self, TokenSequence};
+
+/// Reparses `file` after `edit`, reusing as much of the old tree as possible.
+///
+/// The smallest node, which owns a layer containing the edit, is reparsed in isolation,
+/// provided that the borders of this layer and of the layers of the enclosing nodes,
+/// which contain the edit, do not change. Otherwise, the whole `new_text` is parsed.
+/// In both cases, only the damaged tokens are relexed.
+pub fn reparse(
+    file: &File,
+    edit: &Edit,
+    new_text: &str,
+    tokenizer: &[LexRule],
+    trivia: &Trivia,
+    parser: &Parser,
+) -> (FileStats, INode) {
+    let old_tokens = {
+        let mut tokens = Vec::new();
+        leaf_tokens(&file.inode(), &parser.contextual_keywords(), &mut tokens);
+        tokens
+    };
+    let (lex_time, new_tokens) = measure_time(|| relex(&old_tokens, edit, new_text, tokenizer));
+    let lex_time = lex_time.duration();
+
+    let old = Tokens::new(&old_tokens);
+    let new = Tokens::new(&new_tokens);
+    let path = path_to_edit(file, edit);
+    for (depth, &(node, _)) in path.iter().enumerate().rev() {
+        if let Some((mut stats, inode)) = reparse_node(node, edit, new_text, &old, &new, trivia, parser) {
+            let is_stable = path[..depth].iter()
+                .all(|&(ancestor, _)| is_layer_stable(ancestor, edit, new_text, &old, &new, trivia, parser));
+            if !is_stable {
+                break;
+            }
+            stats.lexing_time = lex_time;
+            let indexes: Vec<usize> = path[..depth + 1].iter().map(|&(_, idx)| idx).collect();
+            let root = replace_node(&file.inode(), &indexes, inode);
+            return (stats, root);
+        }
+    }
+
+    let mut stats = FileStats::new();
+    stats.lexing_time = lex_time;
+    let inode = tree_builder::parse_tokens(new_text, &new_tokens, trivia, &|tokens, stats| parser.parse(tokens, stats), &mut stats);
+    (stats, inode)
+}
+
+/// Tokens together with their offsets.
+struct Tokens<'a> {
+    tokens: &'a [Token],
+    offsets: Vec<TextUnit>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(tokens: &'a [Token]) -> Tokens<'a> {
+        Tokens { tokens, offsets: tree_builder::token_offsets(tokens) }
+    }
+
+    /// Indexes of the tokens which cover exactly the `range`.
+    fn covering(&self, range: TextRange) -> Option<Range<usize>> {
+        let start = self.offsets.binary_search(&range.start()).ok()?;
+        let end = self.offsets.binary_search(&range.end()).ok()?;
+        Some(start..end)
+    }
+}
+
+/// Non-root nodes which strictly contain the edited range, together with their indexes in parents.
+fn path_to_edit<'f>(file: &'f File, edit: &Edit) -> Vec<(Node<'f>, usize)> {
+    let mut result = Vec::new();
+    let mut node = file.root();
+    loop {
+        let child = node.children().enumerate().find(|&(_, child)| {
+            let range = child.range();
+            range.start() < edit.delete.start() && edit.delete.end() < range.end()
+        });
+        match child {
+            Some((idx, child)) => {
+                result.push((child, idx));
+                node = child;
+            }
+            None => return result,
+        }
+    }
+}
+
+fn reparse_node(
+    node: Node,
+    edit: &Edit,
+    new_text: &str,
+    old: &Tokens,
+    new: &Tokens,
+    trivia: &Trivia,
+    parser: &Parser,
+) -> Option<(FileStats, INode)> {
+    let rule = parser.layered_rule(node.ty())?;
+    let mut stats = FileStats::new();
+    let (new_layer, new_range) = edited_layer(rule, node, edit, old, trivia, parser, &mut stats)?;
+    let new_tokens = &new.tokens[new.covering(new_range)?];
+    let new_non_ws = tree_builder::non_ws_indexes(new_tokens, trivia);
+    let new_offsets = tree_builder::token_offsets(new_tokens);
+    let new_text = &new_text[new_range];
+
+    let (parse_time, result) = measure_time(|| {
+        let tokens = TokenSequence::new(new_text, &new_non_ws, new_tokens, &new_offsets);
+        parser.parse_rule(rule, tokens, false, &mut stats)
+    });
+    stats.parsing_time = parse_time.duration();
+    stats.reparsed_region = new_range;
+    match result {
+        Some((node, ref rest, ref layers)) if rest.current().is_none()
+            && layer_ranges(layers, new_tokens, &new_non_ws).contains(&new_layer) => {
+            Some((stats, tree_builder::build_inode(node, new_tokens, trivia)))
+        }
+        _ => None
+    }
+}
+
+/// Whether the borders of the layer of the enclosing `node`, which contains the edit, are moved only by the edit.
+/// Otherwise, the edit changes the structure outside of the reparsed node.
+fn is_layer_stable(node: Node, edit: &Edit, new_text: &str, old: &Tokens, new: &Tokens, trivia: &Trivia, parser: &Parser) -> bool {
+    let rule = match parser.layered_rule(node.ty()) {
+        Some(rule) => rule,
+        None => return true,
+    };
+    let mut stats = FileStats::new();
+    let (new_layer, new_range) = match edited_layer(rule, node, edit, old, trivia, parser, &mut stats) {
+        Some(it) => it,
+        None => return false,
+    };
+    let new_tokens = match new.covering(new_range) {
+        Some(range) => &new.tokens[range],
+        None => return false,
+    };
+    let new_non_ws = tree_builder::non_ws_indexes(new_tokens, trivia);
+    let new_offsets = tree_builder::token_offsets(new_tokens);
+    let tokens = TokenSequence::new(&new_text[new_range], &new_non_ws, new_tokens, &new_offsets);
+    match parser.parse_rule(rule, tokens, true, &mut stats) {
+        Some((_, ref rest, ref layers)) if rest.current().is_none() =>
+            layer_ranges(layers, new_tokens, &new_non_ws).contains(&new_layer),
+        _ => false,
+    }
+}
+
+/// Finds the layer of the `node`, which contains the edit, and returns its range relative to the node
+/// together with the range of the node after the edit.
+fn edited_layer(
+    rule: usize,
+    node: Node,
+    edit: &Edit,
+    old: &Tokens,
+    trivia: &Trivia,
+    parser: &Parser,
+    stats: &mut FileStats,
+) -> Option<(TextRange, TextRange)> {
+    let old_text = node.text().to_string();
+    let old_tokens = &old.tokens[old.covering(node.range())?];
+    let old_non_ws = tree_builder::non_ws_indexes(old_tokens, trivia);
+    let old_offsets = tree_builder::token_offsets(old_tokens);
+    let old_layers = {
+        let tokens = TokenSequence::new(&old_text, &old_non_ws, old_tokens, &old_offsets);
+        match parser.parse_rule(rule, tokens, true, stats) {
+            Some((_, ref rest, ref layers)) if rest.current().is_none() => layer_ranges(layers, old_tokens, &old_non_ws),
+            _ => return None,
+        }
+    };
+
+    let node_start = node.range().start();
+    let delete = TextRange::from_to(edit.delete.start() - node_start, edit.delete.end() - node_start);
+    let old_layer = old_layers.into_iter()
+        .find(|layer| delete.is_subrange_of(*layer))?;
+    let insert_len = TextUnit::measure(&edit.insert);
+    let new_layer = TextRange::from_to(old_layer.start(), old_layer.end() - delete.len() + insert_len);
+    let new_range = TextRange::from_len(node_start, node.range().len() - delete.len() + insert_len);
+    Some((new_layer, new_range))
+}
+
+/// Tokens of the leaves. Contextual keywords are turned back into the tokens, which they are lexed as.
+fn leaf_tokens(node: &INode, keywords: &[(NodeType, NodeType)], acc: &mut Vec<Token>) {
+    if node.children().is_empty() {
+        if node.len() != TextUnit::zero() {
+            let ty = keywords.iter()
+                .find(|&&(keyword, _)| keyword == node.ty())
+                .map_or(node.ty(), |&(_, token)| token);
+            acc.push(Token { ty, len: node.len() })
+        }
+        return;
+    }
+    for child in node.children() {
+        leaf_tokens(child, keywords, acc);
+    }
+}
+
+/// Converts layers from token positions to text ranges, which include surrounding whitespace.
+/// Layers at the end of the node are omitted, because their borders may depend on the following text.
+fn layer_ranges(layers: &[(usize, usize)], tokens: &[Token], non_ws: &[usize]) -> Vec<TextRange> {
+    let offsets = tree_builder::token_offsets(tokens);
+    layers.iter()
+        .filter(|&&(_, end)| end < non_ws.len())
+        .map(|&(start, end)| {
+            let start = if start == 0 { TextUnit::zero() } else { offsets[non_ws[start - 1] + 1] };
+            TextRange::from_to(start, offsets[non_ws[end]])
+        })
+        .collect()
+}
+
+fn replace_node(node: &INode, path: &[usize], replacement: INode) -> INode {
+    match path.split_first() {
+        None => replacement,
+        Some((&idx, rest)) => {
+            let child = replace_node(&node.children()[idx], rest, replacement);
+            let mut node = node.clone();
+            node.replace_child(idx, child);
+            node
+        }
+    }
+}
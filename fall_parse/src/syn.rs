@@ -1,3 +1,5 @@
+use std::collections::{HashMap, VecDeque};
+
 use fall_tree::{NodeType, FileStats};
 use lex::Token;
 
@@ -11,9 +13,21 @@ pub struct Parser<'r> {
 #[derive(Serialize, Deserialize)]
 pub struct SynRule {
     pub body: Expr,
+    /// Whether the results of the rule are memoized, so that backtracking does not parse
+    /// the same tokens with this rule twice.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub memo: bool,
+    /// Whether the rule calls itself without consuming tokens. Such rules are parsed
+    /// by growing the seed: the recursive call reuses the result of the previous iteration.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub left_recursive: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Expr {
     Pub(usize, Box<Expr>),
     Or(Vec<Expr>),
@@ -28,36 +42,111 @@ pub enum Expr {
     Eof,
     Layer(Box<Expr>, Box<Expr>),
     Pratt(Vec<PrattVariant>),
+    /// Elements, separated by the separator, like `<sep_by field ','>`. Tokens, which do not start an element,
+    /// are skipped into an error node, and a missing separator is an empty error node.
+    SepBy(Box<Expr>, Box<Expr>, Trailing),
+    /// A token of the first type with the specified text, like the identifier `union`,
+    /// which becomes a leaf of the second type, like a keyword.
+    ContextualKeyword(usize, String, usize),
+}
+
+/// What `Expr::SepBy` does with a separator at the end of the tokens.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trailing {
+    /// The separator is left to the caller, after an empty error node.
+    Forbid,
+    /// The separator belongs to the list.
+    Allow,
+    /// The separator belongs to the list, wrapped into an error node.
+    Report,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum PrattVariant {
     Atom { body: Box<Expr> },
     Binary {
         ty: usize,
         op: Box<Expr>,
         priority: u32,
-    }
+        /// Right associative operators group `a = b = c` as `a = (b = c)`.
+        #[serde(default, skip_serializing_if = "is_false")]
+        right: bool,
+    },
+    /// An operator before the operand, like unary minus.
+    Prefix {
+        ty: usize,
+        op: Box<Expr>,
+        priority: u32,
+    },
+    /// An operator after the operand, like a call or indexing. The `op` may contain other expressions.
+    Postfix {
+        ty: usize,
+        op: Box<Expr>,
+        priority: u32,
+    },
 }
 
+/// A node parsed by `Parser::parse_rule`, the rest of the tokens and the layers of the node.
+pub type RuleParse<'t> = (Node, TokenSequence<'t>, Vec<(usize, usize)>);
+
 struct Ctx {
     ticks: u64,
     predicate_mode: bool,
+    pub_depth: usize,
+    layers: Vec<(usize, usize)>,
+    memo: HashMap<MemoKey, Option<Memo>>,
+    /// Seeds of the left recursive rules, which are being parsed.
+    seeds: HashMap<SeedKey, Seed>,
+}
+
+/// The rule, the start and the length of the token sequence, the predicate mode and
+/// the public depth, capped at two, because layers are recorded only at the depth of one.
+type MemoKey = (usize, usize, usize, bool, usize);
+
+struct Memo {
+    node: Node,
+    consumed: usize,
+    layers: Vec<(usize, usize)>,
+}
+
+/// Like `MemoKey`, but without the public depth, because the recursive call
+/// of a public rule is one level deeper than the rule itself.
+type SeedKey = (usize, usize, usize, bool);
+
+struct Seed {
+    /// The public depth of the rule call, which grows the seed.
+    pub_depth: usize,
+    memo: Option<Memo>,
 }
 
 impl Ctx {
+    fn new(predicate_mode: bool) -> Ctx {
+        Ctx {
+            ticks: 0,
+            predicate_mode,
+            pub_depth: 0,
+            layers: Vec::new(),
+            memo: HashMap::new(),
+            seeds: HashMap::new(),
+        }
+    }
+
     fn create_composite_node(&mut self, ty: Option<NodeType>) -> Node {
         Node::composite(ty)
     }
 
-    fn create_error_node(&mut self) -> Node {
-        Node::error()
+    fn create_error_node(&mut self, expected: Vec<NodeType>) -> Node {
+        Node::error(expected)
     }
 
     fn create_leaf_node<'t>(&mut self, tokens: TokenSequence<'t>) -> (Node, TokenSequence<'t>) {
         tokens.bump()
     }
 
+    fn create_keyword_node<'t>(&mut self, tokens: TokenSequence<'t>, ty: NodeType) -> (Node, TokenSequence<'t>) {
+        tokens.bump_as(ty)
+    }
+
     fn create_success_node<'t>(&mut self, tokens: TokenSequence<'t>) -> (Node, TokenSequence<'t>) {
         Node::success(tokens)
     }
@@ -81,7 +170,7 @@ impl<'r> Parser<'r> {
     }
 
     pub fn parse(&self, tokens: TokenSequence, stats: &mut FileStats) -> Node {
-        let mut ctx = Ctx { ticks: 0, predicate_mode: false };
+        let mut ctx = Ctx::new(false);
         let (mut file_node, mut leftover) = self
             .parse_exp(&Expr::Rule(0), tokens, &mut ctx)
             .unwrap_or_else(|| {
@@ -92,7 +181,7 @@ impl<'r> Parser<'r> {
                 let ty = self.node_type(ty);
                 (ctx.create_composite_node(Some(ty)), tokens)
             });
-        let mut error = ctx.create_error_node();
+        let mut error = ctx.create_error_node(Vec::new());
         let mut skipped = false;
         while leftover.current().is_some() {
             skipped = true;
@@ -107,17 +196,117 @@ impl<'r> Parser<'r> {
         file_node
     }
 
+    /// Returns the index of the rule which produces nodes of type `ty`,
+    /// if the rule contains a layer and so can be reparsed on its own.
+    pub fn layered_rule(&self, ty: NodeType) -> Option<usize> {
+        let rule = self.rules.iter().position(|r| match r.body {
+            Expr::Pub(idx, _) => self.node_type(idx) == ty,
+            _ => false,
+        });
+        rule.and_then(|rule| {
+            let mut visited = vec![false; self.rules.len()];
+            if self.has_layer(&self.rules[rule].body, &mut visited) { Some(rule) } else { None }
+        })
+    }
+
+    /// Parses a single node with the specified `rule`. Besides the node,
+    /// returns the token positions of all layers that belong directly to this node.
+    /// In predicate mode, the contents of layers are not parsed.
+    pub fn parse_rule<'t>(&self, rule: usize, tokens: TokenSequence<'t>, predicate_mode: bool, stats: &mut FileStats)
+                          -> Option<RuleParse<'t>> {
+        let mut ctx = Ctx::new(predicate_mode);
+        let result = self.parse_exp(&Expr::Rule(rule), tokens, &mut ctx);
+        stats.parsing_ticks += ctx.ticks;
+        result.map(|(node, rest)| (node, rest, ctx.layers))
+    }
+
+    /// Types of the contextual keywords, paired with the types of the tokens, which they are lexed as.
+    pub fn contextual_keywords(&self) -> Vec<(NodeType, NodeType)> {
+        let mut result = Vec::new();
+        for rule in self.rules.iter() {
+            self.collect_keywords(&rule.body, &mut result);
+        }
+        result
+    }
+
+    fn collect_keywords(&self, expr: &Expr, acc: &mut Vec<(NodeType, NodeType)>) {
+        match *expr {
+            Expr::ContextualKeyword(token, _, ty) => {
+                let pair = (self.node_type(ty), self.node_type(token));
+                if !acc.contains(&pair) {
+                    acc.push(pair)
+                }
+            }
+            Expr::Pub(_, ref body) | Expr::Rep(ref body) | Expr::Opt(ref body) | Expr::NotAhead(ref body) =>
+                self.collect_keywords(body, acc),
+            Expr::WithSkip(ref first, ref body) | Expr::Layer(ref first, ref body) | Expr::SepBy(ref first, ref body, _) => {
+                self.collect_keywords(first, acc);
+                self.collect_keywords(body, acc);
+            }
+            Expr::Or(ref parts) | Expr::And(ref parts, _) => for p in parts {
+                self.collect_keywords(p, acc)
+            },
+            Expr::Pratt(ref variants) => for v in variants {
+                match *v {
+                    PrattVariant::Atom { body: ref e } | PrattVariant::Binary { op: ref e, .. }
+                    | PrattVariant::Prefix { op: ref e, .. } | PrattVariant::Postfix { op: ref e, .. } =>
+                        self.collect_keywords(e, acc),
+                }
+            },
+            Expr::Rule(_) | Expr::Token(_) | Expr::Not(_) | Expr::Eof => {}
+        }
+    }
+
+    fn has_layer(&self, expr: &Expr, visited: &mut [bool]) -> bool {
+        match *expr {
+            Expr::Layer(..) => true,
+            Expr::Pub(_, ref body) | Expr::Rep(ref body) | Expr::Opt(ref body) =>
+                self.has_layer(body, visited),
+            Expr::WithSkip(_, ref body) => self.has_layer(body, visited),
+            Expr::SepBy(ref elem, ref sep, _) => self.has_layer(elem, visited) || self.has_layer(sep, visited),
+            Expr::Or(ref parts) | Expr::And(ref parts, _) =>
+                parts.iter().any(|p| self.has_layer(p, visited)),
+            Expr::Rule(id) => {
+                if visited[id] {
+                    return false;
+                }
+                visited[id] = true;
+                match self.rules[id].body {
+                    Expr::Pub(..) => false,
+                    ref body => self.has_layer(body, visited),
+                }
+            }
+            Expr::Token(_) | Expr::Not(_) | Expr::NotAhead(_) | Expr::Eof | Expr::Pratt(_)
+            | Expr::ContextualKeyword(..) => false,
+        }
+    }
+
     fn parse_exp<'t>(&self, expr: &Expr, tokens: TokenSequence<'t>, ctx: &mut Ctx)
                      -> Option<(Node, TokenSequence<'t>)> {
+        let n_layers = ctx.layers.len();
+        let result = self.parse_exp_inner(expr, tokens, ctx);
+        if result.is_none() {
+            ctx.layers.truncate(n_layers);
+        }
+        result
+    }
+
+    fn parse_exp_inner<'t>(&self, expr: &Expr, tokens: TokenSequence<'t>, ctx: &mut Ctx)
+                           -> Option<(Node, TokenSequence<'t>)> {
         ctx.ticks += 1;
         match *expr {
-            Expr::Pub(ty, ref body) => if let Some((node, ts)) = self.parse_exp(body, tokens, ctx) {
-                let mut result = ctx.create_composite_node(Some(self.node_type(ty)));
-                ctx.push_child(&mut result, node);
-                Some((result, ts))
-            } else {
-                None
-            },
+            Expr::Pub(ty, ref body) => {
+                ctx.pub_depth += 1;
+                let result = self.parse_exp(body, tokens, ctx);
+                ctx.pub_depth -= 1;
+                if let Some((node, ts)) = result {
+                    let mut result = ctx.create_composite_node(Some(self.node_type(ty)));
+                    ctx.push_child(&mut result, node);
+                    Some((result, ts))
+                } else {
+                    None
+                }
+            }
 
             Expr::Or(ref parts) => self.parse_any(parts.iter(), tokens, ctx),
 
@@ -133,7 +322,8 @@ impl<'r> Parser<'r> {
                         if i < commit {
                             return None
                         }
-                        let error_node = ctx.create_error_node();
+                        let expected = if ctx.predicate_mode { Vec::new() } else { self.expected(p) };
+                        let error_node = ctx.create_error_node(expected);
                         ctx.push_child(&mut node, error_node);
                         break
                     }
@@ -141,6 +331,8 @@ impl<'r> Parser<'r> {
                 Some((node, tokens))
             }
 
+            Expr::Rule(id) if self.rules[id].left_recursive => self.parse_left_recursive(id, tokens, ctx),
+            Expr::Rule(id) if self.rules[id].memo => self.parse_memo(id, tokens, ctx),
             Expr::Rule(id) => self.parse_exp(&self.rules[id].body, tokens, ctx),
 
             Expr::Token(ty) => {
@@ -152,6 +344,15 @@ impl<'r> Parser<'r> {
                 None
             }
 
+            Expr::ContextualKeyword(token, ref text, ty) => {
+                if let Some(current) = tokens.current() {
+                    if self.token_set_contains(&[token], current) && tokens.current_text() == Some(text.as_str()) {
+                        return Some(ctx.create_keyword_node(tokens, self.node_type(ty)))
+                    }
+                }
+                None
+            }
+
             Expr::Opt(ref body) => self.parse_exp(&*body, tokens, ctx).or_else(|| {
                 Some(ctx.create_success_node(tokens))
             }),
@@ -176,12 +377,18 @@ impl<'r> Parser<'r> {
             },
             Expr::Layer(ref l, ref e) => {
                 if let Some(rest) = self.parse_exp_pred(l, tokens, ctx) {
+                    if ctx.pub_depth == 1 {
+                        ctx.layers.push((tokens.position(), rest.position()));
+                    }
                     let mut result = ctx.create_composite_node(None);
+                    if ctx.predicate_mode {
+                        return Some((result, rest));
+                    }
                     let layer = tokens.prefix(rest);
                     if let Some((layer_contents, mut leftovers)) = self.parse_exp(e, layer, ctx) {
                         ctx.push_child(&mut result, layer_contents);
                         if leftovers.current().is_some() {
-                            let mut error = ctx.create_error_node();
+                            let mut error = ctx.create_error_node(Vec::new());
                             while leftovers.current().is_some() {
                                 let p = ctx.create_leaf_node(leftovers);
                                 ctx.push_child(&mut error, p.0);
@@ -210,7 +417,8 @@ impl<'r> Parser<'r> {
             }
 
             Expr::WithSkip(ref first, ref body) => {
-                let mut error = ctx.create_error_node();
+                let expected = if ctx.predicate_mode { Vec::new() } else { self.expected(first) };
+                let mut error = ctx.create_error_node(expected);
                 let mut result = ctx.create_composite_node(None);
                 let mut skipped = false;
                 let mut tokens = tokens;
@@ -236,7 +444,125 @@ impl<'r> Parser<'r> {
             }
 
             Expr::Pratt(ref g) => self.parse_pratt(&*g, tokens, ctx, 0),
+
+            Expr::SepBy(ref elem, ref sep, trailing) => self.parse_sep_by(elem, sep, trailing, tokens, ctx),
+        }
+    }
+
+    fn parse_sep_by<'t>(&self, elem: &Expr, sep: &Expr, trailing: Trailing, tokens: TokenSequence<'t>, ctx: &mut Ctx)
+                        -> Option<(Node, TokenSequence<'t>)> {
+        let mut result = ctx.create_composite_node(None);
+        let mut tokens = tokens;
+        'elements: while tokens.current().is_some() {
+            let start = tokens;
+            let expected = if ctx.predicate_mode { Vec::new() } else { self.expected(elem) };
+            let mut error = ctx.create_error_node(expected);
+            let mut skipped = false;
+            loop {
+                if let Some((node, ts)) = self.parse_exp(elem, tokens, ctx) {
+                    if skipped {
+                        ctx.push_child(&mut result, error);
+                    }
+                    ctx.push_child(&mut result, node);
+                    tokens = ts;
+                    break;
+                }
+                // Trailing garbage is left to the caller.
+                if tokens.current().is_none() {
+                    tokens = start;
+                    break 'elements;
+                }
+                skipped = true;
+                let (node, ts) = ctx.create_leaf_node(tokens);
+                ctx.push_child(&mut error, node);
+                tokens = ts;
+            }
+            if tokens.current().is_none() {
+                break;
+            }
+            match self.parse_exp(sep, tokens, ctx) {
+                Some((node, ts)) if trailing == Trailing::Allow || ts.current().is_some() => {
+                    ctx.push_child(&mut result, node);
+                    tokens = ts;
+                }
+                Some((node, ts)) if trailing == Trailing::Report => {
+                    let mut error = ctx.create_error_node(Vec::new());
+                    ctx.push_child(&mut error, node);
+                    ctx.push_child(&mut result, error);
+                    tokens = ts;
+                }
+                _ => {
+                    if tokens.position() == start.position() {
+                        break;
+                    }
+                    let expected = if ctx.predicate_mode { Vec::new() } else { self.expected(sep) };
+                    let error = ctx.create_error_node(expected);
+                    ctx.push_child(&mut result, error);
+                }
+            }
+        }
+        Some((result, tokens))
+    }
+
+    fn parse_memo<'t>(&self, id: usize, tokens: TokenSequence<'t>, ctx: &mut Ctx)
+                      -> Option<(Node, TokenSequence<'t>)> {
+        let key = (id, tokens.position(), tokens.len(), ctx.predicate_mode, ctx.pub_depth.min(2));
+        if let Some(memo) = ctx.memo.get(&key) {
+            let layers = &mut ctx.layers;
+            return memo.as_ref().map(|memo| {
+                layers.extend_from_slice(&memo.layers);
+                (memo.node.clone(), tokens.advance(memo.consumed))
+            });
+        }
+        let n_layers = ctx.layers.len();
+        // The node is shared, so that neither storing nor reusing the result copies the subtree.
+        let result = self.parse_exp(&self.rules[id].body, tokens, ctx)
+            .map(|(node, rest)| (node.share(), rest));
+        let memo = result.as_ref().map(|&(ref node, rest)| Memo {
+            node: node.clone(),
+            consumed: rest.position() - tokens.position(),
+            layers: ctx.layers[n_layers..].to_vec(),
+        });
+        ctx.memo.insert(key, memo);
+        result
+    }
+
+    /// Parses the rule repeatedly, while the result grows. The first iteration fails the recursive call,
+    /// and the subsequent ones reuse the result of the previous iteration. Layers of the reused result
+    /// are recorded again only if it is not a nested node, that is, if the rule is called at the same depth.
+    fn parse_left_recursive<'t>(&self, id: usize, tokens: TokenSequence<'t>, ctx: &mut Ctx)
+                                -> Option<(Node, TokenSequence<'t>)> {
+        let key = (id, tokens.position(), tokens.len(), ctx.predicate_mode);
+        if let Some(seed) = ctx.seeds.get(&key) {
+            let same_node = seed.pub_depth == ctx.pub_depth;
+            let layers = &mut ctx.layers;
+            return seed.memo.as_ref().map(|memo| {
+                if same_node {
+                    layers.extend_from_slice(&memo.layers);
+                }
+                (memo.node.clone(), tokens.advance(memo.consumed))
+            });
+        }
+        ctx.seeds.insert(key, Seed { pub_depth: ctx.pub_depth, memo: None });
+        let n_layers = ctx.layers.len();
+        loop {
+            let result = self.parse_exp(&self.rules[id].body, tokens, ctx);
+            let (node, rest) = match result {
+                Some(it) => it,
+                None => break,
+            };
+            let consumed = rest.position() - tokens.position();
+            if ctx.seeds[&key].memo.as_ref().is_some_and(|memo| memo.consumed >= consumed) {
+                break;
+            }
+            let layers = ctx.layers.split_off(n_layers);
+            ctx.seeds.get_mut(&key).unwrap().memo = Some(Memo { node: node.share(), consumed, layers });
         }
+        ctx.layers.truncate(n_layers);
+        ctx.seeds.remove(&key).unwrap().memo.map(|memo| {
+            ctx.layers.extend(memo.layers);
+            (memo.node, tokens.advance(memo.consumed))
+        })
     }
 
     fn parse_any<'t, 'e, I: Iterator<Item=&'e Expr>>(&self, options: I, tokens: TokenSequence<'t>, ctx: &mut Ctx)
@@ -251,56 +577,124 @@ impl<'r> Parser<'r> {
 
     fn parse_pratt<'t>(&self, expr_grammar: &[PrattVariant], tokens: TokenSequence<'t>, ctx: &mut Ctx, min_prior: u32)
                        -> Option<(Node, TokenSequence<'t>)> {
-        let atoms = expr_grammar.iter().filter_map(|v| {
-            match *v {
-                PrattVariant::Atom { ref body } => Some(body.as_ref()),
-                _ => None
-            }
-        });
-
-
-        let (mut lhs, mut tokens) = match self.parse_any(atoms, tokens, ctx) {
+        let (mut lhs, mut tokens) = match self.parse_pratt_prefix(expr_grammar, tokens, ctx) {
             Some(p) => p,
-            None => return None
+            None => {
+                let atoms = expr_grammar.iter().filter_map(|v| {
+                    match *v {
+                        PrattVariant::Atom { ref body } => Some(body.as_ref()),
+                        _ => None
+                    }
+                });
+                self.parse_any(atoms, tokens, ctx)?
+            }
         };
 
         'outer: loop {
-            let bins = expr_grammar.iter().filter_map(|v| {
-                match *v {
-                    PrattVariant::Binary { ty, ref op, priority } if priority > min_prior => {
-                        Some((self.node_type(ty), op.as_ref(), priority))
+            for variant in expr_grammar.iter() {
+                match *variant {
+                    PrattVariant::Postfix { ty, ref op, priority } if priority > min_prior => {
+                        if let Some((op_node, rest)) = self.parse_exp(op, tokens, ctx) {
+                            let mut node = ctx.create_composite_node(Some(self.node_type(ty)));
+                            ::std::mem::swap(&mut node, &mut lhs);
+                            ctx.push_child(&mut lhs, node);
+                            ctx.push_child(&mut lhs, op_node);
+                            tokens = rest;
+                            continue 'outer;
+                        }
                     }
-                    _ => None
+                    PrattVariant::Binary { ty, ref op, priority, right } if priority > min_prior => {
+                        if let Some((op_node, rest)) = self.parse_exp(op, tokens, ctx) {
+                            let rhs_prior = if right { priority - 1 } else { priority };
+                            if let Some((rhs_node, rest)) = self.parse_pratt(expr_grammar, rest, ctx, rhs_prior) {
+                                let mut node = ctx.create_composite_node(Some(self.node_type(ty)));
+                                ::std::mem::swap(&mut node, &mut lhs);
+                                ctx.push_child(&mut lhs, node);
+                                ctx.push_child(&mut lhs, op_node);
+                                ctx.push_child(&mut lhs, rhs_node);
+                                tokens = rest;
+                                continue 'outer;
+                            }
+                        }
+                    }
+                    _ => {}
                 }
-            });
+            }
+            break
+        }
+        Some((lhs, tokens))
+    }
 
-            for (ty, op, p) in bins {
+    /// Parses a prefix operator together with its operand, which includes the operators of higher priority.
+    fn parse_pratt_prefix<'t>(&self, expr_grammar: &[PrattVariant], tokens: TokenSequence<'t>, ctx: &mut Ctx)
+                              -> Option<(Node, TokenSequence<'t>)> {
+        for variant in expr_grammar.iter() {
+            if let PrattVariant::Prefix { ty, ref op, priority } = *variant {
                 if let Some((op_node, rest)) = self.parse_exp(op, tokens, ctx) {
-                    if let Some((rhs_node, rest)) = self.parse_pratt(expr_grammar, rest, ctx, p) {
-                        let mut node = ctx.create_composite_node(Some(ty));
-                        ::std::mem::swap(&mut node, &mut lhs);
-                        ctx.push_child(&mut lhs, node);
-                        ctx.push_child(&mut lhs, op_node);
-                        ctx.push_child(&mut lhs, rhs_node);
-                        tokens = rest;
-                        continue 'outer;
+                    if let Some((operand, rest)) = self.parse_pratt(expr_grammar, rest, ctx, priority) {
+                        let mut node = ctx.create_composite_node(Some(self.node_type(ty)));
+                        ctx.push_child(&mut node, op_node);
+                        ctx.push_child(&mut node, operand);
+                        return Some((node, rest));
                     }
                 }
             }
-            break
         }
-        Some((lhs, tokens))
+        None
     }
 
     fn parse_exp_pred<'t>(&self, expr: &Expr, tokens: TokenSequence<'t>, ctx: &mut Ctx)
                           -> Option<(TokenSequence<'t>)> {
         let old_mode = ctx.predicate_mode;
+        let n_layers = ctx.layers.len();
         ctx.predicate_mode = true;
         let result = self.parse_exp(expr, tokens, ctx);
         ctx.predicate_mode = old_mode;
+        ctx.layers.truncate(n_layers);
         result.map(|(_, ts)| ts)
     }
 
+    /// Tokens or public nodes, which `expr` can start with, for error messages.
+    fn expected(&self, expr: &Expr) -> Vec<NodeType> {
+        let mut result = Vec::new();
+        let mut visited = vec![false; self.rules.len()];
+        self.collect_expected(expr, &mut result, &mut visited);
+        result
+    }
+
+    fn collect_expected(&self, expr: &Expr, acc: &mut Vec<NodeType>, visited: &mut [bool]) {
+        let mut push = |ty: NodeType| if !acc.contains(&ty) { acc.push(ty) };
+        match *expr {
+            Expr::Pub(ty, _) => push(self.node_type(ty)),
+            Expr::Token(ty) | Expr::ContextualKeyword(_, _, ty) => push(self.node_type(ty)),
+            Expr::Rule(id) => {
+                if !visited[id] {
+                    visited[id] = true;
+                    self.collect_expected(&self.rules[id].body, acc, visited)
+                }
+            }
+            Expr::Or(ref parts) => for p in parts {
+                self.collect_expected(p, acc, visited)
+            },
+            Expr::And(ref parts, _) => for p in parts {
+                self.collect_expected(p, acc, visited);
+                if !is_nullable(self.rules, p, &mut vec![false; self.rules.len()]) {
+                    break;
+                }
+            },
+            Expr::Rep(ref body) | Expr::Opt(ref body) | Expr::Layer(ref body, _) | Expr::WithSkip(ref body, _)
+            | Expr::SepBy(ref body, _, _) => self.collect_expected(body, acc, visited),
+            Expr::Pratt(ref variants) => for v in variants {
+                match *v {
+                    PrattVariant::Atom { body: ref e } | PrattVariant::Prefix { op: ref e, .. } =>
+                        self.collect_expected(e, acc, visited),
+                    _ => {}
+                }
+            },
+            Expr::Not(_) | Expr::NotAhead(_) | Expr::Eof => {}
+        }
+    }
+
     fn token_set_contains(&self, ts: &[usize], token: Token) -> bool {
         ts.iter().any(|&t| self.node_type(t) == token.ty)
     }
@@ -309,3 +703,120 @@ impl<'r> Parser<'r> {
         self.node_types[idx]
     }
 }
+
+/// The shortest chain of rules, which starts and ends with `rule`, such that each rule calls the next one
+/// before consuming any tokens. Such a chain of length two is a direct left recursion.
+pub fn left_recursion(rules: &[SynRule], rule: usize) -> Option<Vec<usize>> {
+    let mut parents: Vec<Option<usize>> = vec![None; rules.len()];
+    let mut queue = VecDeque::new();
+    queue.push_back(rule);
+    while let Some(current) = queue.pop_front() {
+        let mut calls = Vec::new();
+        left_calls(rules, &rules[current].body, &mut calls);
+        for next in calls {
+            if next == rule {
+                let mut path = vec![current];
+                let mut node = current;
+                while let Some(parent) = parents[node] {
+                    path.push(parent);
+                    node = parent;
+                }
+                path.reverse();
+                path.push(rule);
+                return Some(path);
+            }
+            if parents[next].is_none() {
+                parents[next] = Some(current);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Rules, which `expr` can call at its start.
+fn left_calls(rules: &[SynRule], expr: &Expr, acc: &mut Vec<usize>) {
+    match *expr {
+        Expr::Rule(id) => if !acc.contains(&id) { acc.push(id) },
+        Expr::Pub(_, ref body) | Expr::Rep(ref body) | Expr::Opt(ref body) | Expr::NotAhead(ref body)
+        | Expr::SepBy(ref body, _, _) => left_calls(rules, body, acc),
+        Expr::WithSkip(ref first, ref body) | Expr::Layer(ref first, ref body) => {
+            left_calls(rules, first, acc);
+            left_calls(rules, body, acc);
+        }
+        Expr::Or(ref parts) => for p in parts {
+            left_calls(rules, p, acc)
+        },
+        Expr::And(ref parts, _) => for p in parts {
+            left_calls(rules, p, acc);
+            if !is_nullable(rules, p, &mut vec![false; rules.len()]) {
+                break;
+            }
+        },
+        Expr::Pratt(ref variants) => for v in variants {
+            match *v {
+                PrattVariant::Atom { body: ref e } | PrattVariant::Prefix { op: ref e, .. } => left_calls(rules, e, acc),
+                _ => {}
+            }
+        },
+        Expr::Token(_) | Expr::Not(_) | Expr::Eof | Expr::ContextualKeyword(..) => {}
+    }
+}
+
+/// Whether `expr` can succeed without consuming tokens.
+fn is_nullable(rules: &[SynRule], expr: &Expr, visited: &mut [bool]) -> bool {
+    match *expr {
+        Expr::Opt(_) | Expr::Rep(_) | Expr::NotAhead(_) | Expr::Eof | Expr::SepBy(..) => true,
+        Expr::Token(_) | Expr::Not(_) | Expr::WithSkip(..) | Expr::Pratt(_) | Expr::ContextualKeyword(..) => false,
+        Expr::Pub(_, ref body) | Expr::Layer(ref body, _) => is_nullable(rules, body, visited),
+        Expr::Or(ref parts) => parts.iter().any(|p| is_nullable(rules, p, visited)),
+        Expr::And(ref parts, _) => parts.iter().all(|p| is_nullable(rules, p, visited)),
+        Expr::Rule(id) => {
+            if visited[id] {
+                return false;
+            }
+            visited[id] = true;
+            is_nullable(rules, &rules[id].body, visited)
+        }
+    }
+}
+
+#[test]
+fn memoization_avoids_exponential_backtracking() {
+    use fall_tree::{WHITESPACE, ERROR};
+    use lex::{LexRule, Trivia};
+    use tree_builder::parse;
+
+    let tokenizer = &[
+        LexRule::new(WHITESPACE, r"\s+", None),
+        LexRule::new(NodeType(10), r"\(", None),
+        LexRule::new(NodeType(11), r"\)", None),
+        LexRule::new(NodeType(12), "x", None),
+        LexRule::new(NodeType(13), "y", None),
+    ];
+    let node_types = &[ERROR, WHITESPACE, NodeType(10), NodeType(11), NodeType(12), NodeType(13), NodeType(20)];
+    // file = a
+    // a = '(' a ')' 'x' | '(' a ')' 'y' | 'x'
+    let rules = |memo: bool| vec![
+        SynRule { body: Expr::Pub(6, Box::new(Expr::Rule(1))), memo: false, left_recursive: false },
+        SynRule {
+            body: Expr::Or(vec![
+                Expr::And(vec![Expr::Token(2), Expr::Rule(1), Expr::Token(3), Expr::Token(4)], None),
+                Expr::And(vec![Expr::Token(2), Expr::Rule(1), Expr::Token(3), Expr::Token(5)], None),
+                Expr::Token(4),
+            ]),
+            memo,
+            left_recursive: false,
+        },
+    ];
+    let text = format!("{}x{}", "(".repeat(12), ")y".repeat(12));
+    let parse_with = |rules: &[SynRule]| {
+        let parser = Parser::new(node_types, rules);
+        parse(&text, tokenizer, &Trivia::new(tokenizer), &|tokens, stats| parser.parse(tokens, stats))
+    };
+    let (plain_stats, plain) = parse_with(&rules(false));
+    let (memo_stats, memo) = parse_with(&rules(true));
+    assert_eq!(format!("{:?}", plain), format!("{:?}", memo));
+    assert!(plain_stats.parsing_ticks > 10_000, "{}", plain_stats.parsing_ticks);
+    assert!(memo_stats.parsing_ticks < 500, "{}", memo_stats.parsing_ticks);
+}
@@ -1,7 +1,9 @@
+use std::rc::Rc;
+
 use elapsed::measure_time;
 
-use fall_tree::{NodeType, ERROR, WHITESPACE, TextRange, FileStats, INode, TextUnit};
-use lex::{Token, LexRule, tokenize};
+use fall_tree::{NodeType, ERROR, TextRange, FileStats, INode, TextUnit};
+use lex::{Token, LexRule, Trivia, tokenize};
 
 #[derive(Clone, Copy, Debug)]
 pub struct TokenSequence<'a> {
@@ -9,38 +11,78 @@ pub struct TokenSequence<'a> {
     start: usize,
     non_ws_indexes: &'a [usize],
     original_tokens: &'a [Token],
+    /// Offsets of the `original_tokens` in the `text`.
+    offsets: &'a [TextUnit],
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Node {
     Leaf(NodeType, usize),
     Composite {
         ty: Option<NodeType>,
         children: Vec<Node>,
-    }
+        /// Expected tokens or nodes, for error nodes.
+        expected: Vec<NodeType>,
+    },
+    /// A memoized subtree, which is shared with the memo table instead of being copied.
+    Shared(Rc<Node>),
 }
 
 impl Node {
-    pub fn error() -> Node {
-        Self::composite(Some(ERROR))
+    pub fn error(expected: Vec<NodeType>) -> Node {
+        Node::Composite { ty: Some(ERROR), children: Vec::new(), expected }
     }
 
     pub fn composite(ty: Option<NodeType>) -> Node {
-        Node::Composite { ty: ty, children: Vec::new() }
+        Node::Composite { ty: ty, children: Vec::new(), expected: Vec::new() }
     }
 
     pub fn success<'t>(ts: TokenSequence<'t>) -> (Node, TokenSequence<'t>) {
         (Self::composite(None), ts)
     }
+
+    /// Makes the node cheap to clone. Leaves and empty nodes are cheap already.
+    pub fn share(self) -> Node {
+        match self {
+            Node::Composite { ref children, .. } if !children.is_empty() => Node::Shared(Rc::new(self)),
+            node => node,
+        }
+    }
 }
 
 impl<'a> TokenSequence<'a> {
+    pub fn new(text: &'a str, non_ws_indexes: &'a [usize], original_tokens: &'a [Token], offsets: &'a [TextUnit])
+               -> TokenSequence<'a> {
+        TokenSequence { text, start: 0, non_ws_indexes, original_tokens, offsets }
+    }
+
+    pub fn position(&self) -> usize {
+        self.start
+    }
+
+    /// The number of tokens, visible to the parser.
+    pub fn len(&self) -> usize {
+        self.non_ws_indexes.len()
+    }
+
+    /// Skips `n` tokens.
+    pub fn advance(&self, n: usize) -> TokenSequence<'a> {
+        TokenSequence {
+            text: self.text,
+            start: self.start + n,
+            non_ws_indexes: &self.non_ws_indexes[n..],
+            original_tokens: self.original_tokens,
+            offsets: self.offsets,
+        }
+    }
+
     pub fn prefix(&self, suffix: TokenSequence<'a>) -> TokenSequence<'a> {
         TokenSequence {
             text: self.text,
             start: self.start,
             non_ws_indexes: &self.non_ws_indexes[..suffix.start - self.start],
-            original_tokens: self.original_tokens
+            original_tokens: self.original_tokens,
+            offsets: self.offsets,
         }
     }
 
@@ -50,16 +92,22 @@ impl<'a> TokenSequence<'a> {
         })
     }
 
+    pub fn current_text(&self) -> Option<&'a str> {
+        self.non_ws_indexes.first().map(|&idx| {
+            &self.text[TextRange::from_len(self.offsets[idx], self.original_tokens[idx].len)]
+        })
+    }
+
     pub fn bump(&self) -> (Node, TokenSequence<'a>) {
         let token = self.current().expect("Can't bump an empty token sequence");
-        let node = Node::Leaf(token.ty, self.non_ws_indexes[0]);
-        let rest = TokenSequence {
-            text: self.text,
-            start: self.start + 1,
-            non_ws_indexes: &self.non_ws_indexes[1..],
-            original_tokens: self.original_tokens,
-        };
-        (node, rest)
+        self.bump_as(token.ty)
+    }
+
+    /// Bumps the current token as a leaf of type `ty`, like an identifier, which is a contextual keyword.
+    pub fn bump_as(&self, ty: NodeType) -> (Node, TokenSequence<'a>) {
+        assert!(self.current().is_some(), "Can't bump an empty token sequence");
+        let node = Node::Leaf(ty, self.non_ws_indexes[0]);
+        (node, self.advance(1))
     }
 }
 
@@ -68,6 +116,7 @@ impl Node {
         match *self {
             Node::Composite { ref mut children, .. } => children.push(child),
             Node::Leaf(..) => panic!("Can't add children to a leaf node"),
+            Node::Shared(ref mut node) => Rc::make_mut(node).push_child(child),
         }
     }
 
@@ -77,7 +126,7 @@ impl Node {
             _ => return "EMPTY-NODE".to_owned()
         };
         let mut result = String::new();
-        let mut start = tokens.original_tokens[..l].iter().map(|t| t.len).sum::<TextUnit>();
+        let mut start = tokens.offsets[l];
         for t in tokens.original_tokens[l..r].iter() {
             result += &tokens.text[TextRange::from_len(start, t.len)];
             start += t.len;
@@ -91,6 +140,7 @@ impl Node {
             Node::Composite { ref children, .. } => children.iter().rev().filter_map(|n| {
                 n.right_idx()
             }).next(),
+            Node::Shared(ref node) => node.right_idx(),
         }
     }
 
@@ -101,6 +151,7 @@ impl Node {
                 None => None,
                 Some(child) => child.left_idx()
             },
+            Node::Shared(ref node) => node.left_idx(),
         }
     }
 }
@@ -108,75 +159,60 @@ impl Node {
 pub fn parse(
     text: &str,
     tokenizer: &[LexRule],
+    trivia: &Trivia,
     parser: &Fn(TokenSequence, &mut FileStats) -> Node
 ) -> (FileStats, INode) {
     let mut stats = FileStats::new();
-    let (lex_time, owned_tokens) = measure_time(|| tokenize(&text, tokenizer).collect::<Vec<_>>());
+    let (lex_time, owned_tokens) = measure_time(|| tokenize(text, tokenizer).collect::<Vec<_>>());
     stats.lexing_time = lex_time.duration();
-    stats.reparsed_region = TextRange::from_to(TextUnit::zero(), TextUnit::from_usize(text.len()));
-    let non_ws_indexes: Vec<usize> = owned_tokens.iter().enumerate().filter_map(|(i, t)| {
-        if t.ty == WHITESPACE { None } else { Some(i) }
-    }).collect();
-    let (parse_time, node) = {
-        let tokens = TokenSequence {
-            text: &text,
-            start: 0,
-            non_ws_indexes: &non_ws_indexes,
-            original_tokens: &owned_tokens,
-        };
-        measure_time(|| parser(tokens, &mut stats))
-    };
-    stats.parsing_time = parse_time.duration();
-
-    let ws_node = to_ws_node(node, &owned_tokens);
-    let inode = ws_node.into_inode().unwrap();
+    let inode = parse_tokens(text, &owned_tokens, trivia, parser, &mut stats);
     (stats, inode)
 }
 
-pub fn reparse(
+pub fn parse_tokens(
     text: &str,
-    tokenizer: &[LexRule],
-    parser: &Fn(TokenSequence, &mut FileStats) -> Option<Node>
-) -> Option<(FileStats, Vec<INode>)> {
-    let mut stats = FileStats::new();
-    let (lex_time, owned_tokens) = measure_time(|| tokenize(&text, tokenizer).collect::<Vec<_>>());
-    stats.lexing_time = lex_time.duration();
+    tokens: &[Token],
+    trivia: &Trivia,
+    parser: &Fn(TokenSequence, &mut FileStats) -> Node,
+    stats: &mut FileStats,
+) -> INode {
     stats.reparsed_region = TextRange::from_to(TextUnit::zero(), TextUnit::from_usize(text.len()));
-    let non_ws_indexes: Vec<usize> = owned_tokens.iter().enumerate().filter_map(|(i, t)| {
-        if t.ty == WHITESPACE { None } else { Some(i) }
-    }).collect();
+    let non_ws_indexes = non_ws_indexes(tokens, trivia);
+    let offsets = token_offsets(tokens);
     let (parse_time, node) = {
-        let tokens = TokenSequence {
-            text: &text,
-            start: 0,
-            non_ws_indexes: &non_ws_indexes,
-            original_tokens: &owned_tokens,
-        };
-        measure_time(|| parser(tokens, &mut stats))
-    };
-    let mut node = match node {
-        Some(node) => node,
-        None => return None,
+        let token_sequence = TokenSequence::new(text, &non_ws_indexes, tokens, &offsets);
+        measure_time(|| parser(token_sequence, stats))
     };
+    stats.parsing_time = parse_time.duration();
 
-    match node {
-        Node::Composite { ref mut ty, ..} => {
-            *ty = Some(WHITESPACE);
-        }
-        _ => {}
-    }
+    build_inode(node, tokens, trivia)
+}
 
-    stats.parsing_time = parse_time.duration();
+/// Indexes of the tokens which are visible to the parser, that is, of all non-trivia tokens.
+pub fn non_ws_indexes(tokens: &[Token], trivia: &Trivia) -> Vec<usize> {
+    tokens.iter().enumerate().filter_map(|(i, t)| {
+        if trivia.contains(t.ty) { None } else { Some(i) }
+    }).collect()
+}
 
-    let ws_node = to_ws_node(node, &owned_tokens);
-    let inode = ws_node.into_inode().unwrap();
-    let children = inode.children().iter().cloned().collect();
-    Some((stats, children))
+/// Offsets of the starts of the `tokens`, followed by the offset of the end of the last one.
+pub fn token_offsets(tokens: &[Token]) -> Vec<TextUnit> {
+    let mut result = vec![TextUnit::zero()];
+    for t in tokens {
+        let last = *result.last().unwrap();
+        result.push(last + t.len);
+    }
+    result
+}
+
+pub fn build_inode(node: Node, tokens: &[Token], trivia: &Trivia) -> INode {
+    to_ws_node(node, tokens, trivia).into_inode().unwrap()
 }
 
 #[derive(Debug)]
 struct WsNode {
     ty: Option<NodeType>,
+    expected: Vec<NodeType>,
     len: TextUnit,
     children: Vec<WsNode>,
     first: Option<usize>,
@@ -184,12 +220,12 @@ struct WsNode {
 }
 
 impl WsNode {
-    fn push_child(&mut self, child: WsNode, tokens: &[Token]) {
+    fn push_child(&mut self, child: WsNode, tokens: &[Token], trivia: &Trivia) {
         match (self.last, child.first) {
             (Some(l), Some(r)) if l + 1 < r => {
                 for idx in l + 1..r {
                     let t = tokens[idx];
-                    assert!(t.ty == WHITESPACE, "expected whitespace, got {:?}", t.ty);
+                    assert!(trivia.contains(t.ty), "expected trivia, got {:?}", t.ty);
                     self.push_child_raw(token_pre_node(idx, t))
                 }
             }
@@ -207,7 +243,12 @@ impl WsNode {
 
     fn attach_to_inode(self, parent: &mut INode) {
         if self.children.is_empty() {
-            parent.push_child(INode::new_leaf(self.ty.unwrap(), self.len));
+            // Lexer errors are leaves, parser errors are empty composite nodes.
+            let node = match self.ty.unwrap() {
+                ERROR if self.len == TextUnit::zero() => INode::new_error(self.expected),
+                ty => INode::new_leaf(ty, self.len),
+            };
+            parent.push_child(node);
             return;
         }
         match self.into_inode() {
@@ -222,7 +263,7 @@ impl WsNode {
 
     fn into_inode(self) -> Result<INode, WsNode> {
         if let Some(ty) = self.ty {
-            let mut node = INode::new(ty);
+            let mut node = if ty == ERROR { INode::new_error(self.expected) } else { INode::new(ty) };
             for child in self.children {
                 child.attach_to_inode(&mut node);
             }
@@ -236,6 +277,7 @@ impl WsNode {
 fn token_pre_node(idx: usize, t: Token) -> WsNode {
     WsNode {
         ty: Some(t.ty),
+        expected: Vec::new(),
         len: t.len,
         children: Vec::new(),
         first: Some(idx),
@@ -243,13 +285,18 @@ fn token_pre_node(idx: usize, t: Token) -> WsNode {
     }
 }
 
-fn to_ws_node(file_node: Node, tokens: &[Token]) -> WsNode {
+fn to_ws_node(file_node: Node, tokens: &[Token], trivia: &Trivia) -> WsNode {
+    let file_node = match file_node {
+        Node::Shared(node) => Rc::try_unwrap(node).unwrap_or_else(|node| (*node).clone()),
+        node => node,
+    };
     let (ty, children) = match file_node {
         Node::Composite { ty, children, .. } => (ty.unwrap(), children),
         _ => panic!("Root node must be composite")
     };
     let mut result = WsNode {
         ty: Some(ty),
+        expected: Vec::new(),
         len: TextUnit::zero(),
         children: Vec::new(),
         first: None,
@@ -257,42 +304,45 @@ fn to_ws_node(file_node: Node, tokens: &[Token]) -> WsNode {
     };
 
     for (i, &t) in tokens.iter().enumerate() {
-        if t.ty != WHITESPACE {
+        if !trivia.contains(t.ty) {
             break
         }
         result.push_child_raw(token_pre_node(i, t))
     }
 
     for child in children {
-        add_child(&mut result, &child, tokens)
+        add_child(&mut result, &child, tokens, trivia)
     }
     if let Some(idx) = result.last {
         for idx in idx + 1..tokens.len() {
             let t = tokens[idx];
-            assert!(t.ty == WHITESPACE);
+            assert!(trivia.contains(t.ty));
             result.push_child_raw(token_pre_node(idx, t))
         }
     }
     result
 }
 
-fn add_child(parent: &mut WsNode, node: &Node, tokens: &[Token]) {
+fn add_child(parent: &mut WsNode, node: &Node, tokens: &[Token], trivia: &Trivia) {
     match *node {
-        Node::Leaf(_, idx) => {
-            parent.push_child(token_pre_node(idx, tokens[idx]), tokens)
+        Node::Leaf(ty, idx) => {
+            let token = Token { ty, len: tokens[idx].len };
+            parent.push_child(token_pre_node(idx, token), tokens, trivia)
         }
-        Node::Composite { ty, ref children} => {
+        Node::Composite { ty, ref children, ref expected } => {
             let mut p = WsNode {
                 ty: ty,
+                expected: expected.clone(),
                 len: TextUnit::zero(),
                 children: Vec::new(),
                 first: None,
                 last: None,
             };
             for child in children {
-                add_child(&mut p, child, tokens);
+                add_child(&mut p, child, tokens, trivia);
             }
-            parent.push_child(p, tokens)
+            parent.push_child(p, tokens, trivia)
         }
+        Node::Shared(ref node) => add_child(parent, node, tokens, trivia),
     }
 }
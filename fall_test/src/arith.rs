@@ -1,73 +1,288 @@
 use serde_json;
-use fall_tree::{NodeType, NodeTypeInfo, Language, LanguageImpl, FileStats, INode};
+use fall_tree::{NodeType, NodeTypeInfo, Language, LanguageImpl, Injection, FileStats, INode, File, Edit};
+use fall_tree::registry::Registry;
 pub use fall_tree::{ERROR, WHITESPACE};
 
-pub const NUMBER: NodeType = NodeType(100);
-pub const PLUS: NodeType = NodeType(101);
-pub const MINUS: NodeType = NodeType(102);
-pub const STAR: NodeType = NodeType(103);
-pub const SLASH: NodeType = NodeType(104);
-pub const LPAREN: NodeType = NodeType(105);
-pub const RPAREN: NodeType = NodeType(106);
-pub const FILE: NodeType = NodeType(107);
-pub const PRODUCT_EXPR: NodeType = NodeType(108);
-pub const SUM_EXPR: NodeType = NodeType(109);
-pub const CONSTANT_EXPR: NodeType = NodeType(110);
-pub const PAREN_EXPR: NodeType = NodeType(111);
+pub const NUMBER: NodeType = NodeType((47586 << 16) | 100);
+pub const PLUS: NodeType = NodeType((47586 << 16) | 101);
+pub const MINUS: NodeType = NodeType((47586 << 16) | 102);
+pub const STAR: NodeType = NodeType((47586 << 16) | 103);
+pub const SLASH: NodeType = NodeType((47586 << 16) | 104);
+pub const CARET: NodeType = NodeType((47586 << 16) | 105);
+pub const BANG: NodeType = NodeType((47586 << 16) | 106);
+pub const LPAREN: NodeType = NodeType((47586 << 16) | 107);
+pub const LBRACK: NodeType = NodeType((47586 << 16) | 108);
+pub const RBRACK: NodeType = NodeType((47586 << 16) | 109);
+pub const RPAREN: NodeType = NodeType((47586 << 16) | 110);
+pub const FILE: NodeType = NodeType((47586 << 16) | 111);
+pub const FACTORIAL_EXPR: NodeType = NodeType((47586 << 16) | 112);
+pub const INDEX_EXPR: NodeType = NodeType((47586 << 16) | 113);
+pub const POWER_EXPR: NodeType = NodeType((47586 << 16) | 114);
+pub const NEG_EXPR: NodeType = NodeType((47586 << 16) | 115);
+pub const PRODUCT_EXPR: NodeType = NodeType((47586 << 16) | 116);
+pub const SUM_EXPR: NodeType = NodeType((47586 << 16) | 117);
+pub const CONSTANT_EXPR: NodeType = NodeType((47586 << 16) | 118);
+pub const PAREN_EXPR: NodeType = NodeType((47586 << 16) | 119);
 
 lazy_static! {
     pub static ref LANG: Language = {
-        use fall_parse::{LexRule, SynRule, Parser};
+        use fall_parse::{LexRule, Trivia, SynRule, Parser};
         const ALL_NODE_TYPES: &[NodeType] = &[
             ERROR, WHITESPACE,
-            NUMBER, PLUS, MINUS, STAR, SLASH, LPAREN, RPAREN, FILE, PRODUCT_EXPR, SUM_EXPR, CONSTANT_EXPR, PAREN_EXPR,
+            NUMBER, PLUS, MINUS, STAR, SLASH, CARET, BANG, LPAREN, LBRACK, RBRACK, RPAREN, FILE, FACTORIAL_EXPR, INDEX_EXPR, POWER_EXPR, NEG_EXPR, PRODUCT_EXPR, SUM_EXPR, CONSTANT_EXPR, PAREN_EXPR,
         ];
-        let parser_json = r##"[{"body":{"Pub":[9,{"Or":[{"And":[[{"Rule":1}],null]}]}]}},{"body":{"Pratt":[{"Binary":{"ty":11,"op":{"Or":[{"And":[[{"Token":3}],null]},{"And":[[{"Token":4}],null]}]},"priority":1}},{"Binary":{"ty":10,"op":{"Or":[{"And":[[{"Token":5}],null]},{"And":[[{"Token":6}],null]}]},"priority":2}},{"Atom":{"body":{"Pub":[12,{"Or":[{"And":[[{"Token":2}],null]}]}]}}},{"Atom":{"body":{"Pub":[13,{"Or":[{"And":[[{"Token":7},{"Rule":1},{"Token":8}],null]}]}]}}}]}},{"body":{"Pub":[10,{"Or":[{"And":[[{"Rule":1},{"Or":[{"And":[[{"Token":5}],null]},{"And":[[{"Token":6}],null]}]},{"Rule":1}],null]}]}]}},{"body":{"Pub":[11,{"Or":[{"And":[[{"Rule":1},{"Or":[{"And":[[{"Token":3}],null]},{"And":[[{"Token":4}],null]}]},{"Rule":1}],null]}]}]}},{"body":{"Pub":[12,{"Or":[{"And":[[{"Token":2}],null]}]}]}},{"body":{"Pub":[13,{"Or":[{"And":[[{"Token":7},{"Rule":1},{"Token":8}],null]}]}]}}]"##;
+        let parser_json = r##"[{"body":{"Pub":[13,{"Or":[{"And":[[{"Rule":1}],null]}]}]}},{"body":{"Pratt":[{"Binary":{"ty":19,"op":{"Or":[{"And":[[{"Token":3}],null]},{"And":[[{"Token":4}],null]}]},"priority":1}},{"Binary":{"ty":18,"op":{"Or":[{"And":[[{"Token":5}],null]},{"And":[[{"Token":6}],null]}]},"priority":2}},{"Binary":{"ty":16,"op":{"Token":7},"priority":4,"right":true}},{"Prefix":{"ty":17,"op":{"And":[[{"Token":4}],null]},"priority":3}},{"Postfix":{"ty":14,"op":{"And":[[{"Token":8}],null]},"priority":5}},{"Postfix":{"ty":15,"op":{"And":[[{"Token":10},{"Rule":1},{"Token":11}],null]},"priority":5}},{"Atom":{"body":{"Pub":[20,{"Or":[{"And":[[{"Token":2}],null]}]}]}}},{"Atom":{"body":{"Pub":[21,{"Or":[{"And":[[{"Token":9},{"Rule":1},{"Token":12}],null]}]}]}}}]}},{"body":{"Pub":[14,{"Or":[{"And":[[{"Rule":1},{"Token":8}],null]}]}]}},{"body":{"Pub":[15,{"Or":[{"And":[[{"Rule":1},{"Token":10},{"Rule":1},{"Token":11}],null]}]}]}},{"body":{"Pub":[16,{"Or":[{"And":[[{"Rule":1},{"Token":7},{"Rule":1}],null]}]}]}},{"body":{"Pub":[17,{"Or":[{"And":[[{"Token":4},{"Rule":1}],null]}]}]}},{"body":{"Pub":[18,{"Or":[{"And":[[{"Rule":1},{"Or":[{"And":[[{"Token":5}],null]},{"And":[[{"Token":6}],null]}]},{"Rule":1}],null]}]}]}},{"body":{"Pub":[19,{"Or":[{"And":[[{"Rule":1},{"Or":[{"And":[[{"Token":3}],null]},{"And":[[{"Token":4}],null]}]},{"Rule":1}],null]}]}]}},{"body":{"Pub":[20,{"Or":[{"And":[[{"Token":2}],null]}]}]}},{"body":{"Pub":[21,{"Or":[{"And":[[{"Token":9},{"Rule":1},{"Token":12}],null]}]}]}}]"##;
         let parser: Vec<SynRule> = serde_json::from_str(parser_json).unwrap();
 
-        struct Impl { tokenizer: Vec<LexRule>, parser: Vec<SynRule> };
+        struct Impl { tokenizer: Vec<LexRule>, trivia: Trivia, parser: Vec<SynRule> };
         impl LanguageImpl for Impl {
+            fn name(&self) -> &'static str {
+                "arith"
+            }
+
+            fn extensions(&self) -> &'static [&'static str] {
+                &[]
+            }
+
+            fn version(&self) -> u64 {
+                6488845216573019206
+            }
+
             fn parse(&self, text: &str) -> (FileStats, INode) {
-                ::fall_parse::parse(text, &self.tokenizer, &|tokens, stats| {
+                ::fall_parse::parse(text, &self.tokenizer, &self.trivia, &|tokens, stats| {
                     Parser::new(ALL_NODE_TYPES, &self.parser).parse(tokens, stats)
                 })
             }
 
+            fn reparse(&self, file: &File, edit: &Edit, new_text: &str) -> (FileStats, INode) {
+                let parser = Parser::new(ALL_NODE_TYPES, &self.parser);
+                ::fall_parse::reparse(file, edit, new_text, &self.tokenizer, &self.trivia, &parser)
+            }
+
+            fn is_trivia(&self, ty: NodeType) -> bool {
+                self.trivia.contains(ty)
+            }
+
+            fn injections(&self) -> &'static [Injection] {
+                &[
+                    
+                ]
+            }
+
+            fn node_types(&self) -> &'static [NodeType] {
+                ALL_NODE_TYPES
+            }
+
             fn node_type_info(&self, ty: NodeType) -> NodeTypeInfo {
                 match ty {
-                    ERROR => NodeTypeInfo { name: "ERROR" },
-                    WHITESPACE => NodeTypeInfo { name: "WHITESPACE" },
-                    NUMBER => NodeTypeInfo { name: "NUMBER" },
-                    PLUS => NodeTypeInfo { name: "PLUS" },
-                    MINUS => NodeTypeInfo { name: "MINUS" },
-                    STAR => NodeTypeInfo { name: "STAR" },
-                    SLASH => NodeTypeInfo { name: "SLASH" },
-                    LPAREN => NodeTypeInfo { name: "LPAREN" },
-                    RPAREN => NodeTypeInfo { name: "RPAREN" },
-                    FILE => NodeTypeInfo { name: "FILE" },
-                    PRODUCT_EXPR => NodeTypeInfo { name: "PRODUCT_EXPR" },
-                    SUM_EXPR => NodeTypeInfo { name: "SUM_EXPR" },
-                    CONSTANT_EXPR => NodeTypeInfo { name: "CONSTANT_EXPR" },
-                    PAREN_EXPR => NodeTypeInfo { name: "PAREN_EXPR" },
+                    ERROR => NodeTypeInfo {
+                        name: "ERROR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    WHITESPACE => NodeTypeInfo {
+                        name: "WHITESPACE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    NUMBER => NodeTypeInfo {
+                        name: "NUMBER",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: true,
+                        text: None,
+                        classes: &[],
+                    },
+                    PLUS => NodeTypeInfo {
+                        name: "PLUS",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("+"),
+                        classes: &[],
+                    },
+                    MINUS => NodeTypeInfo {
+                        name: "MINUS",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("-"),
+                        classes: &[],
+                    },
+                    STAR => NodeTypeInfo {
+                        name: "STAR",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("*"),
+                        classes: &[],
+                    },
+                    SLASH => NodeTypeInfo {
+                        name: "SLASH",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("/"),
+                        classes: &[],
+                    },
+                    CARET => NodeTypeInfo {
+                        name: "CARET",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("^"),
+                        classes: &[],
+                    },
+                    BANG => NodeTypeInfo {
+                        name: "BANG",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("!"),
+                        classes: &[],
+                    },
+                    LPAREN => NodeTypeInfo {
+                        name: "LPAREN",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("("),
+                        classes: &[],
+                    },
+                    LBRACK => NodeTypeInfo {
+                        name: "LBRACK",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("["),
+                        classes: &[],
+                    },
+                    RBRACK => NodeTypeInfo {
+                        name: "RBRACK",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("]"),
+                        classes: &[],
+                    },
+                    RPAREN => NodeTypeInfo {
+                        name: "RPAREN",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some(")"),
+                        classes: &[],
+                    },
+                    FILE => NodeTypeInfo {
+                        name: "FILE",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    FACTORIAL_EXPR => NodeTypeInfo {
+                        name: "FACTORIAL_EXPR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    INDEX_EXPR => NodeTypeInfo {
+                        name: "INDEX_EXPR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    POWER_EXPR => NodeTypeInfo {
+                        name: "POWER_EXPR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    NEG_EXPR => NodeTypeInfo {
+                        name: "NEG_EXPR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    PRODUCT_EXPR => NodeTypeInfo {
+                        name: "PRODUCT_EXPR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    SUM_EXPR => NodeTypeInfo {
+                        name: "SUM_EXPR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    CONSTANT_EXPR => NodeTypeInfo {
+                        name: "CONSTANT_EXPR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    PAREN_EXPR => NodeTypeInfo {
+                        name: "PAREN_EXPR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
                     _ => panic!("Unknown NodeType: {:?}", ty)
                 }
             }
         }
 
+        let tokenizer = vec![
+            LexRule::new(WHITESPACE, "\\s+", None),
+            LexRule::new(NUMBER, "\\d+", None),
+            LexRule::new(PLUS, "\\+", None),
+            LexRule::new(MINUS, "\\-", None),
+            LexRule::new(STAR, "\\*", None),
+            LexRule::new(SLASH, "/", None),
+            LexRule::new(CARET, "\\^", None),
+            LexRule::new(BANG, "!", None),
+            LexRule::new(LPAREN, "\\(", None),
+            LexRule::new(LBRACK, "\\[", None),
+            LexRule::new(RBRACK, "\\]", None),
+            LexRule::new(RPAREN, "\\)", None),
+        ];
         Language::new(Impl {
-            tokenizer: vec![
-                LexRule::new(WHITESPACE, "\\s+", None),
-                LexRule::new(NUMBER, "\\d+", None),
-                LexRule::new(PLUS, "\\+", None),
-                LexRule::new(MINUS, "\\-", None),
-                LexRule::new(STAR, "\\*", None),
-                LexRule::new(SLASH, "/", None),
-                LexRule::new(LPAREN, "\\(", None),
-                LexRule::new(RPAREN, "\\)", None),
-            ],
+            trivia: Trivia::new(&tokenizer),
+            tokenizer,
             parser: parser,
         })
     };
 }
 
+/// Adds the language to the `registry`.
+/// Use `register(&mut fall_tree::registry::global())` to make it available process-wide.
+pub fn register(registry: &mut Registry) {
+    registry.register(LANG.clone())
+}
+
+
 
@@ -1,16 +1,17 @@
 use serde_json;
-use fall_tree::{NodeType, NodeTypeInfo, Language, LanguageImpl, FileStats, INode};
+use fall_tree::{NodeType, NodeTypeInfo, Language, LanguageImpl, Injection, FileStats, INode, File, Edit};
+use fall_tree::registry::Registry;
 pub use fall_tree::{ERROR, WHITESPACE};
 
-pub const LPAREN: NodeType = NodeType(100);
-pub const RPAREN: NodeType = NodeType(101);
-pub const ATOM: NodeType = NodeType(102);
-pub const FILE: NodeType = NodeType(103);
-pub const LIST: NodeType = NodeType(104);
+pub const LPAREN: NodeType = NodeType((36038 << 16) | 100);
+pub const RPAREN: NodeType = NodeType((36038 << 16) | 101);
+pub const ATOM: NodeType = NodeType((36038 << 16) | 102);
+pub const FILE: NodeType = NodeType((36038 << 16) | 103);
+pub const LIST: NodeType = NodeType((36038 << 16) | 104);
 
 lazy_static! {
     pub static ref LANG: Language = {
-        use fall_parse::{LexRule, SynRule, Parser};
+        use fall_parse::{LexRule, Trivia, SynRule, Parser};
         const ALL_NODE_TYPES: &[NodeType] = &[
             ERROR, WHITESPACE,
             LPAREN, RPAREN, ATOM, FILE, LIST,
@@ -18,38 +19,127 @@ lazy_static! {
         let parser_json = r##"[{"body":{"Pub":[5,{"Or":[{"And":[[{"Rep":{"Or":[{"And":[[{"Rule":1}],null]}]}}],null]}]}]}},{"body":{"Or":[{"And":[[{"Token":4}],null]},{"And":[[{"Rule":2}],null]}]}},{"body":{"Pub":[6,{"Or":[{"And":[[{"Token":2},{"Rep":{"Or":[{"And":[[{"Rule":1}],null]}]}},{"Token":3}],null]}]}]}}]"##;
         let parser: Vec<SynRule> = serde_json::from_str(parser_json).unwrap();
 
-        struct Impl { tokenizer: Vec<LexRule>, parser: Vec<SynRule> };
+        struct Impl { tokenizer: Vec<LexRule>, trivia: Trivia, parser: Vec<SynRule> };
         impl LanguageImpl for Impl {
+            fn name(&self) -> &'static str {
+                "sexp"
+            }
+
+            fn extensions(&self) -> &'static [&'static str] {
+                &[]
+            }
+
+            fn version(&self) -> u64 {
+                2174844402605605861
+            }
+
             fn parse(&self, text: &str) -> (FileStats, INode) {
-                ::fall_parse::parse(text, &self.tokenizer, &|tokens, stats| {
+                ::fall_parse::parse(text, &self.tokenizer, &self.trivia, &|tokens, stats| {
                     Parser::new(ALL_NODE_TYPES, &self.parser).parse(tokens, stats)
                 })
             }
 
+            fn reparse(&self, file: &File, edit: &Edit, new_text: &str) -> (FileStats, INode) {
+                let parser = Parser::new(ALL_NODE_TYPES, &self.parser);
+                ::fall_parse::reparse(file, edit, new_text, &self.tokenizer, &self.trivia, &parser)
+            }
+
+            fn is_trivia(&self, ty: NodeType) -> bool {
+                self.trivia.contains(ty)
+            }
+
+            fn injections(&self) -> &'static [Injection] {
+                &[
+                    
+                ]
+            }
+
+            fn node_types(&self) -> &'static [NodeType] {
+                ALL_NODE_TYPES
+            }
+
             fn node_type_info(&self, ty: NodeType) -> NodeTypeInfo {
                 match ty {
-                    ERROR => NodeTypeInfo { name: "ERROR" },
-                    WHITESPACE => NodeTypeInfo { name: "WHITESPACE" },
-                    LPAREN => NodeTypeInfo { name: "LPAREN" },
-                    RPAREN => NodeTypeInfo { name: "RPAREN" },
-                    ATOM => NodeTypeInfo { name: "ATOM" },
-                    FILE => NodeTypeInfo { name: "FILE" },
-                    LIST => NodeTypeInfo { name: "LIST" },
+                    ERROR => NodeTypeInfo {
+                        name: "ERROR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    WHITESPACE => NodeTypeInfo {
+                        name: "WHITESPACE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    LPAREN => NodeTypeInfo {
+                        name: "LPAREN",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("("),
+                        classes: &[],
+                    },
+                    RPAREN => NodeTypeInfo {
+                        name: "RPAREN",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some(")"),
+                        classes: &[],
+                    },
+                    ATOM => NodeTypeInfo {
+                        name: "ATOM",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    FILE => NodeTypeInfo {
+                        name: "FILE",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    LIST => NodeTypeInfo {
+                        name: "LIST",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
                     _ => panic!("Unknown NodeType: {:?}", ty)
                 }
             }
         }
 
+        let tokenizer = vec![
+            LexRule::new(LPAREN, "\\(", None),
+            LexRule::new(RPAREN, "\\)", None),
+            LexRule::new(WHITESPACE, "\\s+", None),
+            LexRule::new(ATOM, "\\w+", None),
+        ];
         Language::new(Impl {
-            tokenizer: vec![
-                LexRule::new(LPAREN, "\\(", None),
-                LexRule::new(RPAREN, "\\)", None),
-                LexRule::new(WHITESPACE, "\\s+", None),
-                LexRule::new(ATOM, "\\w+", None),
-            ],
+            trivia: Trivia::new(&tokenizer),
+            tokenizer,
             parser: parser,
         })
     };
 }
 
+/// Adds the language to the `registry`.
+/// Use `register(&mut fall_tree::registry::global())` to make it available process-wide.
+pub fn register(registry: &mut Registry) {
+    registry.register(LANG.clone())
+}
+
+
 
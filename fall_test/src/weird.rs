@@ -1,81 +1,367 @@
 use serde_json;
-use fall_tree::{NodeType, NodeTypeInfo, Language, LanguageImpl, FileStats, INode};
+use fall_tree::{NodeType, NodeTypeInfo, Language, LanguageImpl, Injection, FileStats, INode, File, Edit};
+use fall_tree::registry::Registry;
 pub use fall_tree::{ERROR, WHITESPACE};
 
-pub const RAW_STRING: NodeType = NodeType(100);
-pub const FOO: NodeType = NodeType(101);
-pub const BAR: NodeType = NodeType(102);
-pub const T1: NodeType = NodeType(103);
-pub const T2: NodeType = NodeType(104);
-pub const T3: NodeType = NodeType(105);
-pub const T4: NodeType = NodeType(106);
-pub const LBRACE: NodeType = NodeType(107);
-pub const RBRACE: NodeType = NodeType(108);
-pub const ATOM: NodeType = NodeType(109);
-pub const FILE: NodeType = NodeType(110);
-pub const PRIVATE_PARTIAL: NodeType = NodeType(111);
-pub const EMPTY: NodeType = NodeType(112);
-pub const BLOCK: NodeType = NodeType(113);
+pub const RAW_STRING: NodeType = NodeType((14362 << 16) | 100);
+pub const FOO: NodeType = NodeType((14362 << 16) | 101);
+pub const BAR: NodeType = NodeType((14362 << 16) | 102);
+pub const T1: NodeType = NodeType((14362 << 16) | 103);
+pub const T2: NodeType = NodeType((14362 << 16) | 104);
+pub const T3: NodeType = NodeType((14362 << 16) | 105);
+pub const T4: NodeType = NodeType((14362 << 16) | 106);
+pub const T5: NodeType = NodeType((14362 << 16) | 107);
+pub const T6: NodeType = NodeType((14362 << 16) | 108);
+pub const T7: NodeType = NodeType((14362 << 16) | 109);
+pub const T8: NodeType = NodeType((14362 << 16) | 110);
+pub const T9: NodeType = NodeType((14362 << 16) | 111);
+pub const COMMA: NodeType = NodeType((14362 << 16) | 112);
+pub const BANG: NodeType = NodeType((14362 << 16) | 113);
+pub const LBRACE: NodeType = NodeType((14362 << 16) | 114);
+pub const RBRACE: NodeType = NodeType((14362 << 16) | 115);
+pub const ATOM: NodeType = NodeType((14362 << 16) | 116);
+pub const UNION: NodeType = NodeType((14362 << 16) | 117);
+pub const FILE: NodeType = NodeType((14362 << 16) | 118);
+pub const PRIVATE_PARTIAL: NodeType = NodeType((14362 << 16) | 119);
+pub const EMPTY: NodeType = NodeType((14362 << 16) | 120);
+pub const BLOCK: NodeType = NodeType((14362 << 16) | 121);
+pub const LIST: NodeType = NodeType((14362 << 16) | 122);
+pub const POSTFIX: NodeType = NodeType((14362 << 16) | 123);
+pub const SEP_LIST: NodeType = NodeType((14362 << 16) | 124);
+pub const STRICT_SEP_LIST: NodeType = NodeType((14362 << 16) | 125);
+pub const UNION_DEF: NodeType = NodeType((14362 << 16) | 126);
+pub const GROUP_LIST: NodeType = NodeType((14362 << 16) | 127);
 
 lazy_static! {
     pub static ref LANG: Language = {
-        use fall_parse::{LexRule, SynRule, Parser};
+        use fall_parse::{LexRule, Trivia, SynRule, Parser};
         const ALL_NODE_TYPES: &[NodeType] = &[
             ERROR, WHITESPACE,
-            RAW_STRING, FOO, BAR, T1, T2, T3, T4, LBRACE, RBRACE, ATOM, FILE, PRIVATE_PARTIAL, EMPTY, BLOCK,
+            RAW_STRING, FOO, BAR, T1, T2, T3, T4, T5, T6, T7, T8, T9, COMMA, BANG, LBRACE, RBRACE, ATOM, UNION, FILE, PRIVATE_PARTIAL, EMPTY, BLOCK, LIST, POSTFIX, SEP_LIST, STRICT_SEP_LIST, UNION_DEF, GROUP_LIST,
         ];
-        let parser_json = r##"[{"body":{"Pub":[12,{"Or":[{"And":[[{"Token":5},{"Token":2}],null]},{"And":[[{"Token":6},{"Rule":4},{"Token":11},{"Rule":4}],null]},{"And":[[{"Token":7},{"Rule":1}],null]},{"And":[[{"Token":8},{"Rule":6}],null]}]}]}},{"body":{"Pub":[13,{"Or":[{"And":[[{"Rule":2}],null]},{"And":[[{"Rule":3}],null]}]}]}},{"body":{"Or":[{"And":[[{"Token":3},{"Token":4}],null]}]}},{"body":{"Or":[{"And":[[{"Token":3},{"Token":3}],null]}]}},{"body":{"Pub":[14,{"Or":[{"And":[[{"Opt":{"Or":[{"And":[[{"Rule":5}],null]}]}}],null]}]}]}},{"body":{"Or":[{"And":[[],null]}]}},{"body":{"Pub":[15,{"Or":[{"And":[[{"Token":9},{"Rule":7},{"Token":10}],1]}]}]}},{"body":{"Or":[{"And":[[{"Rep":{"Rule":8}}],null]}]}},{"body":{"Or":[{"And":[[{"Token":9},{"Rule":7},{"Token":10}],1]},{"And":[[{"Not":[10]}],null]}]}}]"##;
+        let parser_json = r##"[{"body":{"Pub":[20,{"Or":[{"And":[[{"Token":5},{"Token":2}],null]},{"And":[[{"Token":6},{"Rule":4},{"Token":18},{"Rule":4}],null]},{"And":[[{"Token":7},{"Rule":1}],null]},{"And":[[{"Token":8},{"Rule":6}],null]},{"And":[[{"Token":9},{"Rule":9}],null]},{"And":[[{"Token":10},{"Rule":11}],null]},{"And":[[{"Token":11},{"Rep":{"Or":[{"And":[[{"Rule":13}],null]},{"And":[[{"Token":18}],null]}]}}],null]},{"And":[[{"Token":12},{"Rule":12}],null]},{"And":[[{"Token":13},{"Rule":14}],null]}]}]}},{"body":{"Pub":[21,{"Or":[{"And":[[{"Rule":2}],null]},{"And":[[{"Rule":3}],null]}]}]}},{"body":{"Or":[{"And":[[{"Token":3},{"Token":4}],null]}]}},{"body":{"Or":[{"And":[[{"Token":3},{"Token":3}],null]}]}},{"body":{"Pub":[22,{"Or":[{"And":[[{"Opt":{"Or":[{"And":[[{"Rule":5}],null]}]}}],null]}]}]}},{"body":{"Or":[{"And":[[],null]}]}},{"body":{"Pub":[23,{"Or":[{"And":[[{"Token":16},{"Rule":17},{"Token":17}],1]}]}]}},{"body":{"Or":[]}},{"body":{"Or":[]}},{"body":{"Pub":[24,{"Or":[{"And":[[{"Rule":9},{"Token":14},{"Rule":10}],null]},{"And":[[{"Rule":10}],null]}]}]},"left_recursive":true},{"body":{"Pub":[25,{"Or":[{"And":[[{"Rule":10},{"Token":15}],null]},{"And":[[{"Token":18}],null]}]}]},"left_recursive":true},{"body":{"Pub":[26,{"Or":[{"And":[[{"SepBy":[{"Token":18},{"Token":14},"Allow"]}],null]}]}]}},{"body":{"Pub":[27,{"Or":[{"And":[[{"SepBy":[{"Token":18},{"Token":14},"Report"]}],null]}]}]}},{"body":{"Pub":[28,{"Or":[{"And":[[{"ContextualKeyword":[18,"union",19]},{"Token":18}],null]}]}]}},{"body":{"Pub":[29,{"Or":[{"And":[[{"Rule":15}],null]}]}]}},{"body":{"Or":[{"And":[[{"Rule":15},{"Token":14},{"Rule":16}],null]},{"And":[[{"Rule":16}],null]}]},"left_recursive":true},{"body":{"Or":[{"And":[[{"Token":16},{"Layer":[{"Rule":17},{"Rep":{"Token":18}}]},{"Token":17}],null]}]}},{"body":{"Or":[{"And":[[{"Rep":{"Rule":18}}],null]}]}},{"body":{"Or":[{"And":[[{"Token":16},{"Rule":17},{"Token":17}],1]},{"And":[[{"Not":[17]}],null]}]}}]"##;
         let parser: Vec<SynRule> = serde_json::from_str(parser_json).unwrap();
 
-        struct Impl { tokenizer: Vec<LexRule>, parser: Vec<SynRule> };
+        struct Impl { tokenizer: Vec<LexRule>, trivia: Trivia, parser: Vec<SynRule> };
         impl LanguageImpl for Impl {
+            fn name(&self) -> &'static str {
+                "weird"
+            }
+
+            fn extensions(&self) -> &'static [&'static str] {
+                &[]
+            }
+
+            fn version(&self) -> u64 {
+                14837956909722278299
+            }
+
             fn parse(&self, text: &str) -> (FileStats, INode) {
-                ::fall_parse::parse(text, &self.tokenizer, &|tokens, stats| {
+                ::fall_parse::parse(text, &self.tokenizer, &self.trivia, &|tokens, stats| {
                     Parser::new(ALL_NODE_TYPES, &self.parser).parse(tokens, stats)
                 })
             }
 
+            fn reparse(&self, file: &File, edit: &Edit, new_text: &str) -> (FileStats, INode) {
+                let parser = Parser::new(ALL_NODE_TYPES, &self.parser);
+                ::fall_parse::reparse(file, edit, new_text, &self.tokenizer, &self.trivia, &parser)
+            }
+
+            fn is_trivia(&self, ty: NodeType) -> bool {
+                self.trivia.contains(ty)
+            }
+
+            fn injections(&self) -> &'static [Injection] {
+                &[
+                    
+                ]
+            }
+
+            fn node_types(&self) -> &'static [NodeType] {
+                ALL_NODE_TYPES
+            }
+
             fn node_type_info(&self, ty: NodeType) -> NodeTypeInfo {
                 match ty {
-                    ERROR => NodeTypeInfo { name: "ERROR" },
-                    WHITESPACE => NodeTypeInfo { name: "WHITESPACE" },
-                    RAW_STRING => NodeTypeInfo { name: "RAW_STRING" },
-                    FOO => NodeTypeInfo { name: "FOO" },
-                    BAR => NodeTypeInfo { name: "BAR" },
-                    T1 => NodeTypeInfo { name: "T1" },
-                    T2 => NodeTypeInfo { name: "T2" },
-                    T3 => NodeTypeInfo { name: "T3" },
-                    T4 => NodeTypeInfo { name: "T4" },
-                    LBRACE => NodeTypeInfo { name: "LBRACE" },
-                    RBRACE => NodeTypeInfo { name: "RBRACE" },
-                    ATOM => NodeTypeInfo { name: "ATOM" },
-                    FILE => NodeTypeInfo { name: "FILE" },
-                    PRIVATE_PARTIAL => NodeTypeInfo { name: "PRIVATE_PARTIAL" },
-                    EMPTY => NodeTypeInfo { name: "EMPTY" },
-                    BLOCK => NodeTypeInfo { name: "BLOCK" },
+                    ERROR => NodeTypeInfo {
+                        name: "ERROR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    WHITESPACE => NodeTypeInfo {
+                        name: "WHITESPACE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    RAW_STRING => NodeTypeInfo {
+                        name: "RAW_STRING",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    FOO => NodeTypeInfo {
+                        name: "FOO",
+                        is_token: true,
+                        is_keyword: true,
+                        is_literal: false,
+                        text: Some("foo"),
+                        classes: &[],
+                    },
+                    BAR => NodeTypeInfo {
+                        name: "BAR",
+                        is_token: true,
+                        is_keyword: true,
+                        is_literal: false,
+                        text: Some("bar"),
+                        classes: &[],
+                    },
+                    T1 => NodeTypeInfo {
+                        name: "T1",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("_1"),
+                        classes: &[],
+                    },
+                    T2 => NodeTypeInfo {
+                        name: "T2",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("_2"),
+                        classes: &[],
+                    },
+                    T3 => NodeTypeInfo {
+                        name: "T3",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("_3"),
+                        classes: &[],
+                    },
+                    T4 => NodeTypeInfo {
+                        name: "T4",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("_4"),
+                        classes: &[],
+                    },
+                    T5 => NodeTypeInfo {
+                        name: "T5",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("_5"),
+                        classes: &[],
+                    },
+                    T6 => NodeTypeInfo {
+                        name: "T6",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("_6"),
+                        classes: &[],
+                    },
+                    T7 => NodeTypeInfo {
+                        name: "T7",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("_7"),
+                        classes: &[],
+                    },
+                    T8 => NodeTypeInfo {
+                        name: "T8",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("_8"),
+                        classes: &[],
+                    },
+                    T9 => NodeTypeInfo {
+                        name: "T9",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("_9"),
+                        classes: &[],
+                    },
+                    COMMA => NodeTypeInfo {
+                        name: "COMMA",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some(","),
+                        classes: &[],
+                    },
+                    BANG => NodeTypeInfo {
+                        name: "BANG",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("!"),
+                        classes: &[],
+                    },
+                    LBRACE => NodeTypeInfo {
+                        name: "LBRACE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("{"),
+                        classes: &[],
+                    },
+                    RBRACE => NodeTypeInfo {
+                        name: "RBRACE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("}"),
+                        classes: &[],
+                    },
+                    ATOM => NodeTypeInfo {
+                        name: "ATOM",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    UNION => NodeTypeInfo {
+                        name: "UNION",
+                        is_token: true,
+                        is_keyword: true,
+                        is_literal: false,
+                        text: Some("union"),
+                        classes: &[],
+                    },
+                    FILE => NodeTypeInfo {
+                        name: "FILE",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    PRIVATE_PARTIAL => NodeTypeInfo {
+                        name: "PRIVATE_PARTIAL",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    EMPTY => NodeTypeInfo {
+                        name: "EMPTY",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    BLOCK => NodeTypeInfo {
+                        name: "BLOCK",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    LIST => NodeTypeInfo {
+                        name: "LIST",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    POSTFIX => NodeTypeInfo {
+                        name: "POSTFIX",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    SEP_LIST => NodeTypeInfo {
+                        name: "SEP_LIST",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    STRICT_SEP_LIST => NodeTypeInfo {
+                        name: "STRICT_SEP_LIST",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    UNION_DEF => NodeTypeInfo {
+                        name: "UNION_DEF",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    GROUP_LIST => NodeTypeInfo {
+                        name: "GROUP_LIST",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
                     _ => panic!("Unknown NodeType: {:?}", ty)
                 }
             }
         }
 
+        let tokenizer = vec![
+            LexRule::new(WHITESPACE, "\\s+", None),
+            LexRule::new(RAW_STRING, "r#+\"", Some(parse_raw_string)),
+            LexRule::new(FOO, "foo", None),
+            LexRule::new(BAR, "bar", None),
+            LexRule::new(T1, "_1", None),
+            LexRule::new(T2, "_2", None),
+            LexRule::new(T3, "_3", None),
+            LexRule::new(T4, "_4", None),
+            LexRule::new(T5, "_5", None),
+            LexRule::new(T6, "_6", None),
+            LexRule::new(T7, "_7", None),
+            LexRule::new(T8, "_8", None),
+            LexRule::new(T9, "_9", None),
+            LexRule::new(COMMA, ",", None),
+            LexRule::new(BANG, "!", None),
+            LexRule::new(LBRACE, "\\{", None),
+            LexRule::new(RBRACE, "\\}", None),
+            LexRule::new(ATOM, "\\w+", None),
+        ];
         Language::new(Impl {
-            tokenizer: vec![
-                LexRule::new(WHITESPACE, "\\s+", None),
-                LexRule::new(RAW_STRING, "r#+\"", Some(parse_raw_string)),
-                LexRule::new(FOO, "foo", None),
-                LexRule::new(BAR, "bar", None),
-                LexRule::new(T1, "_1", None),
-                LexRule::new(T2, "_2", None),
-                LexRule::new(T3, "_3", None),
-                LexRule::new(T4, "_4", None),
-                LexRule::new(LBRACE, "\\{", None),
-                LexRule::new(RBRACE, "\\}", None),
-                LexRule::new(ATOM, "\\w+", None),
-            ],
+            trivia: Trivia::new(&tokenizer),
+            tokenizer,
             parser: parser,
         })
     };
 }
+
+/// Adds the language to the `registry`.
+/// Use `register(&mut fall_tree::registry::global())` to make it available process-wide.
+pub fn register(registry: &mut Registry) {
+    registry.register(LANG.clone())
+}
+
 fn parse_raw_string(s: &str) -> Option<usize> {
     let quote_start = s.find('"').unwrap();
     let q_hashes = concat!('"', "######", "######", "######", "######", "######");
@@ -91,3 +91,69 @@ FILE
       NUMBER "4"
 "#);
 }
+
+#[test]
+fn right_associativity() {
+    match_ast(&ast("1 ^ 2 ^ 3"), r#"
+FILE
+  POWER_EXPR
+    CONSTANT_EXPR
+      NUMBER "1"
+    CARET "^"
+    POWER_EXPR
+      CONSTANT_EXPR
+        NUMBER "2"
+      CARET "^"
+      CONSTANT_EXPR
+        NUMBER "3"
+"#);
+}
+
+#[test]
+fn prefix() {
+    match_ast(&ast("- 1 * - - 2 ^ 3"), r#"
+FILE
+  PRODUCT_EXPR
+    NEG_EXPR
+      MINUS "-"
+      CONSTANT_EXPR
+        NUMBER "1"
+    STAR "*"
+    NEG_EXPR
+      MINUS "-"
+      NEG_EXPR
+        MINUS "-"
+        POWER_EXPR
+          CONSTANT_EXPR
+            NUMBER "2"
+          CARET "^"
+          CONSTANT_EXPR
+            NUMBER "3"
+"#);
+}
+
+#[test]
+fn postfix() {
+    match_ast(&ast("- 1 ! [2 + 3] ^ 4"), r#"
+FILE
+  NEG_EXPR
+    MINUS "-"
+    POWER_EXPR
+      INDEX_EXPR
+        FACTORIAL_EXPR
+          CONSTANT_EXPR
+            NUMBER "1"
+          BANG "!"
+        LBRACK "["
+        SUM_EXPR
+          CONSTANT_EXPR
+            NUMBER "2"
+          PLUS "+"
+          CONSTANT_EXPR
+            NUMBER "3"
+        RBRACK "]"
+      CARET "^"
+      CONSTANT_EXPR
+        NUMBER "4"
+"#);
+}
@@ -0,0 +1,105 @@
+extern crate fall_test;
+extern crate fall_tree;
+extern crate lang_fall;
+extern crate lang_rust;
+
+use fall_test::{sexp, arith, match_ast};
+use fall_tree::{dump_file, Edit, TextRange, TextUnit, WHITESPACE};
+use fall_tree::registry;
+use fall_tree::serialize::{to_bytes, from_bytes, Error};
+use fall_tree::rewrite::{Rewriter, Fragment};
+use fall_tree::search::{child_of_type_exn, find_leaf_at_offset};
+use lang_fall::{LANG_FALL, highlight};
+
+#[test]
+fn node_types_of_different_languages_are_distinct() {
+    assert_ne!(sexp::LPAREN, arith::LPAREN);
+    assert!(sexp::LANG.owns(sexp::LPAREN));
+    assert!(!sexp::LANG.owns(arith::LPAREN));
+    assert!(!sexp::LANG.owns(WHITESPACE));
+    assert_ne!(sexp::LANG.tag(), arith::LANG.tag());
+}
+
+#[test]
+fn mixed_tree() {
+    let file = sexp::LANG.parse("(a b)".to_owned());
+    let list = child_of_type_exn(file.root(), sexp::LIST);
+    let atom = child_of_type_exn(list, sexp::ATOM);
+    let expr = Fragment::parse(&arith::LANG, "1 + 2", arith::SUM_EXPR).unwrap();
+    let mut rewriter = Rewriter::new(&file);
+    rewriter.replace(atom, expr);
+    let (file, _) = rewriter.finish();
+
+    assert_eq!(file.languages().len(), 2);
+    match_ast(&dump_file(&file), r#"
+FILE
+  LIST
+    LPAREN "("
+    SUM_EXPR
+      CONSTANT_EXPR
+        NUMBER "1"
+      PLUS "+"
+      CONSTANT_EXPR
+        NUMBER "2"
+    ATOM "b"
+    RPAREN ")"
+"#);
+    let leaf = |offset: u32| find_leaf_at_offset(file.root(), TextUnit::from_usize(offset as usize)).right_biased().unwrap();
+    assert_eq!(leaf(0).language().name(), "sexp");
+    assert_eq!(leaf(1).language().name(), "arith");
+    assert_eq!(leaf(2).ty(), WHITESPACE);
+    assert_eq!(leaf(2).language().name(), "arith");
+    assert_eq!(leaf(6).ty(), WHITESPACE);
+    assert_eq!(leaf(6).language().name(), "sexp");
+}
+
+#[test]
+fn injection() {
+    lang_rust::register(&mut registry::global());
+    let file = LANG_FALL.parse(r##"verbatim r#"fn f() {}"#"##.to_owned());
+    assert_eq!(file.languages().len(), 2);
+    match_ast(&dump_file(&file), r##"
+FALL_FILE
+  VERBATIM_DEF
+    KW_VERBATIM "verbatim"
+    HASH_STRING
+      HASH_STRING "r#\""
+      FILE
+        FN_DEF
+          FN "fn"
+          IDENT "f"
+          LPAREN "("
+          RPAREN ")"
+          BLOCK_EXPR
+            LBRACE "{"
+            RBRACE "}"
+      HASH_STRING "\"#"
+"##);
+    let fn_kw = find_leaf_at_offset(file.root(), TextUnit::from_usize(13)).right_biased().unwrap();
+    assert_eq!(fn_kw.ty(), lang_rust::FN);
+    assert_eq!(fn_kw.range(), TextRange::from_to(TextUnit::from_usize(12), TextUnit::from_usize(14)));
+    assert!(highlight(&file).contains(&(12, 14, "keyword")));
+
+    let edit = Edit { delete: TextRange::from_to(TextUnit::from_usize(15), TextUnit::from_usize(16)), insert: "g".to_owned() };
+    let file = file.edit(&edit);
+    assert_eq!(file.text().to_string(), r##"verbatim r#"fn g() {}"#"##);
+    let ident = find_leaf_at_offset(file.root(), TextUnit::from_usize(15)).right_biased().unwrap();
+    assert_eq!(ident.ty(), lang_rust::IDENT);
+    assert_eq!(ident.language().name(), "rust");
+}
+
+#[test]
+fn binary_roundtrip_of_injection() {
+    lang_rust::register(&mut registry::global());
+    let file = LANG_FALL.parse(r##"verbatim r#"fn f() {}"#"##.to_owned());
+    let restored = from_bytes(&LANG_FALL, &to_bytes(&file)).unwrap();
+    assert_eq!(restored.languages().len(), 2);
+    assert_eq!(dump_file(&restored), dump_file(&file));
+
+    let file = sexp::LANG.parse("(a)".to_owned());
+    let atom = child_of_type_exn(child_of_type_exn(file.root(), sexp::LIST), sexp::ATOM);
+    let mut rewriter = Rewriter::new(&file);
+    rewriter.replace(atom, Fragment::parse(&arith::LANG, "1", arith::CONSTANT_EXPR).unwrap());
+    let (file, _) = rewriter.finish();
+    assert_eq!(from_bytes(&sexp::LANG, &to_bytes(&file)).err(), Some(Error::UnknownLanguage { name: "arith".to_owned() }));
+}
@@ -0,0 +1,34 @@
+extern crate fall_test;
+extern crate fall_tree;
+extern crate lang_json;
+
+use std::path::Path;
+
+use fall_test::{sexp, arith};
+use fall_tree::registry::{self, Registry};
+
+#[test]
+fn lookup_by_name_and_extension() {
+    let mut registry = Registry::new();
+    sexp::register(&mut registry);
+    arith::register(&mut registry);
+    lang_json::register(&mut registry);
+
+    assert_eq!(registry.languages().len(), 3);
+    assert_eq!(registry.by_name("sexp").unwrap().name(), "sexp");
+    assert!(registry.by_name("rust").is_none());
+    assert_eq!(registry.by_extension("json").unwrap().name(), "json");
+    assert_eq!(registry.for_path(Path::new("data/config.json")).unwrap().name(), "json");
+    assert!(registry.for_path(Path::new("Makefile")).is_none());
+
+    sexp::register(&mut registry);
+    assert_eq!(registry.languages().len(), 3);
+}
+
+#[test]
+fn global_registry() {
+    lang_json::register(&mut registry::global());
+    let lang = registry::global().by_extension("json").unwrap().clone();
+    let file = lang.parse("{}".to_owned());
+    assert_eq!(file.language().name(), "json");
+}
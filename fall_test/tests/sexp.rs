@@ -1,8 +1,9 @@
 extern crate fall_test;
 extern crate fall_tree;
 
-use fall_test::{sexp, match_ast};
+use fall_test::{sexp, arith, match_ast};
 use fall_tree::dump_file_ws;
+use fall_tree::serialize::{to_bytes, from_bytes, to_json, Error, FORMAT_VERSION};
 
 
 fn ast(code: &str) -> String {
@@ -48,3 +49,31 @@ FILE
     RPAREN ")"
 "#)
 }
+
+#[test]
+fn binary_roundtrip() {
+    let file = sexp::LANG.parse("(a (b c) \n d)".to_owned());
+    let bytes = to_bytes(&file);
+    let restored = from_bytes(&sexp::LANG, &bytes).unwrap();
+    assert_eq!(restored.text().to_string(), file.text().to_string());
+    assert_eq!(dump_file_ws(&restored), dump_file_ws(&file));
+
+    let broken = sexp::LANG.parse("(a (b".to_owned());
+    let restored = from_bytes(&sexp::LANG, &to_bytes(&broken)).unwrap();
+    assert!(!broken.errors().is_empty());
+    assert_eq!(restored.errors(), broken.errors());
+
+    assert_eq!(from_bytes(&arith::LANG, &bytes).err(), Some(Error::WrongLanguage { name: "sexp".to_owned() }));
+    assert_eq!(from_bytes(&sexp::LANG, &bytes[..bytes.len() - 1]).err(), Some(Error::Corrupted));
+    let mut stale = bytes.clone();
+    stale[4] += 1;
+    assert_eq!(from_bytes(&sexp::LANG, &stale).err(), Some(Error::StaleFormat { version: FORMAT_VERSION + 1 }));
+}
+
+#[test]
+fn json_export() {
+    let file = sexp::LANG.parse("(a)".to_owned());
+    let json = to_json(&file);
+    let expected = r#"{"language":"sexp","root":{"children":[{"children":[{"range":[0,1],"text":"(","type":"LPAREN"},{"range":[1,2],"text":"a","type":"ATOM"},{"range":[2,3],"text":")","type":"RPAREN"}],"range":[0,3],"type":"LIST"}],"range":[0,3],"type":"FILE"},"version":""#;
+    assert!(json.starts_with(expected), "{}", json);
+}
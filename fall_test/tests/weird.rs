@@ -3,6 +3,7 @@ extern crate fall_tree;
 
 use fall_test::{weird, match_ast};
 use fall_tree::dump_file_ws;
+use fall_tree::test_util::check_reparse;
 
 
 fn ast(code: &str) -> String {
@@ -135,3 +136,110 @@ FILE
     ERROR ""
 "#);
 }
+
+#[test]
+fn left_recursion() {
+    match_ast(&ast("_5 a, b!!, c"), r#"
+FILE
+  T5 "_5"
+  WHITESPACE " "
+  LIST
+    LIST
+      LIST
+        POSTFIX
+          ATOM "a"
+      COMMA ","
+      WHITESPACE " "
+      POSTFIX
+        POSTFIX
+          POSTFIX
+            ATOM "b"
+          BANG "!"
+        BANG "!"
+    COMMA ","
+    WHITESPACE " "
+    POSTFIX
+      ATOM "c"
+"#);
+}
+
+#[test]
+fn separated_list() {
+    match_ast(&ast("_6 a b, !, c,"), r#"
+FILE
+  T6 "_6"
+  WHITESPACE " "
+  SEP_LIST
+    ATOM "a"
+    ERROR ""
+    WHITESPACE " "
+    ATOM "b"
+    COMMA ","
+    WHITESPACE " "
+    ERROR
+      BANG "!"
+      COMMA ","
+    WHITESPACE " "
+    ATOM "c"
+    COMMA ","
+"#);
+}
+
+#[test]
+fn separated_list_reporting_trailing() {
+    match_ast(&ast("_8 a, b,"), r#"
+FILE
+  T8 "_8"
+  WHITESPACE " "
+  STRICT_SEP_LIST
+    ATOM "a"
+    COMMA ","
+    WHITESPACE " "
+    ATOM "b"
+    ERROR
+      COMMA ","
+"#);
+}
+
+#[test]
+fn contextual_keyword() {
+    match_ast(&ast("_7 union union union"), r#"
+FILE
+  T7 "_7"
+  WHITESPACE " "
+  UNION_DEF
+    UNION "union"
+    WHITESPACE " "
+    ATOM "union"
+  WHITESPACE " "
+  ATOM "union"
+"#);
+}
+
+#[test]
+fn contextual_keyword_reparse() {
+    check_reparse(&weird::LANG, "_7 union a x", "_7 union a y", r#"
+FILE
+  T7 "_7"
+  UNION_DEF
+    UNION "union"
+    ATOM "a"
+  ATOM "y"
+"#, "_7 union a y");
+}
+
+#[test]
+fn left_recursion_reparse() {
+    check_reparse(&weird::LANG, "_9 {a}, {b}", "_9 {c}, {b}", r#"
+FILE
+  T9 "_9"
+  GROUP_LIST
+    LBRACE "{"
+    ATOM "c"
+    RBRACE "}"
+    COMMA ","
+    LBRACE "{"
+    ATOM "b"
+    RBRACE "}"
+"#, "{c}, {b}");
+}
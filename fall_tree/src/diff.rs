@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use {File, Node};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Change<'o, 'n> {
+    Insert(Node<'n>),
+    Delete(Node<'o>),
+    Replace(Node<'o>, Node<'n>),
+}
+
+pub struct TreeDiff<'o, 'n> {
+    changes: Vec<Change<'o, 'n>>,
+    mapping: HashMap<Node<'o>, Node<'n>>,
+}
+
+impl<'o, 'n> TreeDiff<'o, 'n> {
+    /// Changed subtrees, in the document order.
+    pub fn changes(&self) -> &[Change<'o, 'n>] {
+        &self.changes
+    }
+
+    /// Returns the node of the new tree which corresponds to the `old` node,
+    /// if the node survived the change.
+    pub fn new_node(&self, old: Node<'o>) -> Option<Node<'n>> {
+        self.mapping.get(&old).cloned()
+    }
+
+    pub fn mapping(&self) -> &HashMap<Node<'o>, Node<'n>> {
+        &self.mapping
+    }
+}
+
+/// Computes the changes which turn the `old` tree into the `new` one.
+///
+/// Identical subtrees are matched first. Among the rest, composite nodes of the same type
+/// are considered to be the same node with modified contents and are diffed recursively.
+/// Tokens with a different text and all other unmatched nodes are reported as changes.
+pub fn diff<'o, 'n>(old: &'o File, new: &'n File) -> TreeDiff<'o, 'n> {
+    let mut result = TreeDiff { changes: Vec::new(), mapping: HashMap::new() };
+    let (old, new) = (old.root(), new.root());
+    if old.ty() == new.ty() {
+        go(old, new, &mut result);
+    } else {
+        result.changes.push(Change::Replace(old, new));
+    }
+    return result;
+
+    fn go<'o, 'n>(old: Node<'o>, new: Node<'n>, acc: &mut TreeDiff<'o, 'n>) {
+        acc.mapping.insert(old, new);
+        let old_children: Vec<Node> = old.children().collect();
+        let new_children: Vec<Node> = new.children().collect();
+        let anchors = lcs(&old_children, &new_children, |&a, &b| a.ty() == b.ty() && a.text() == b.text());
+
+        let (mut i, mut j) = (0, 0);
+        for (ai, aj) in anchors.into_iter().chain(Some((old_children.len(), new_children.len()))) {
+            diff_gap(&old_children[i..ai], &new_children[j..aj], acc);
+            if ai < old_children.len() {
+                go(old_children[ai], new_children[aj], acc);
+            }
+            i = ai + 1;
+            j = aj + 1;
+        }
+    }
+
+    fn diff_gap<'o, 'n>(old: &[Node<'o>], new: &[Node<'n>], acc: &mut TreeDiff<'o, 'n>) {
+        let anchors = lcs(old, new, |&a, &b| a.ty() == b.ty());
+        let (mut i, mut j) = (0, 0);
+        for (ai, aj) in anchors.into_iter().chain(Some((old.len(), new.len()))) {
+            let (old_gap, new_gap) = (&old[i..ai], &new[j..aj]);
+            for k in 0..::std::cmp::max(old_gap.len(), new_gap.len()) {
+                acc.changes.push(match (old_gap.get(k), new_gap.get(k)) {
+                    (Some(&o), Some(&n)) => Change::Replace(o, n),
+                    (Some(&o), None) => Change::Delete(o),
+                    (None, Some(&n)) => Change::Insert(n),
+                    (None, None) => unreachable!(),
+                });
+            }
+            if ai < old.len() {
+                let (o, n) = (old[ai], new[aj]);
+                if is_token(o) || is_token(n) {
+                    acc.changes.push(Change::Replace(o, n));
+                } else {
+                    go(o, n, acc);
+                }
+            }
+            i = ai + 1;
+            j = aj + 1;
+        }
+    }
+
+    fn is_token(node: Node) -> bool {
+        node.children().next().is_none()
+    }
+}
+
+/// Longest common subsequence of `a` and `b`, as a list of pairs of indexes.
+fn lcs<A: Copy, B: Copy, F: Fn(&A, &B) -> bool>(a: &[A], b: &[B], eq: F) -> Vec<(usize, usize)> {
+    let prefix = a.iter().zip(b.iter()).take_while(|&(x, y)| eq(x, y)).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|&(x, y)| eq(x, y)).count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let (n, m) = (a_mid.len(), b_mid.len());
+    let mut table = vec![0u32; (n + 1) * (m + 1)];
+    let idx = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[idx(i, j)] = if eq(&a_mid[i], &b_mid[j]) {
+                table[idx(i + 1, j + 1)] + 1
+            } else {
+                ::std::cmp::max(table[idx(i + 1, j)], table[idx(i, j + 1)])
+            }
+        }
+    }
+
+    let mut result: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if eq(&a_mid[i], &b_mid[j]) {
+            result.push((prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if table[idx(i + 1, j)] >= table[idx(i, j + 1)] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result.extend((0..suffix).map(|k| (a.len() - suffix + k, b.len() - suffix + k)));
+    result
+}
@@ -0,0 +1,151 @@
+//! Declarative formatting.
+//!
+//! A language describes the whitespace between adjacent tokens with a list of rules, like
+//! "one space after `COLON` inside `FIELD`", and the formatter turns them into edits of the whitespace.
+//! Gaps without a matching rule, gaps with comments and gaps inside error nodes are left as is.
+
+use {File, Node, NodeType, TextRange, Edit, ERROR};
+use search::ancestors;
+use tokens::Token;
+use indent::Indenter;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Spacing {
+    /// No whitespace at all.
+    Empty,
+    /// A single space.
+    Space,
+    /// A line break, followed by the indentation. A single blank line is preserved.
+    Newline,
+}
+
+/// Whitespace between two adjacent tokens. Tokens are described by the types of the nodes,
+/// which end with the left token or start with the right one, the parent is the smallest node
+/// which contains both tokens. Missing constraints match anything.
+#[derive(Clone, Copy, Debug)]
+pub struct Rule {
+    parent: Option<NodeType>,
+    after: Option<NodeType>,
+    before: Option<NodeType>,
+    spacing: Spacing,
+    /// Whether the rule skips gaps with line breaks.
+    inline: bool,
+}
+
+impl Rule {
+    pub fn anywhere() -> Rule {
+        Rule { parent: None, after: None, before: None, spacing: Spacing::Space, inline: false }
+    }
+
+    pub fn inside(parent: NodeType) -> Rule {
+        Rule { parent: Some(parent), ..Rule::anywhere() }
+    }
+
+    pub fn after(self, ty: NodeType) -> Rule {
+        Rule { after: Some(ty), ..self }
+    }
+
+    pub fn before(self, ty: NodeType) -> Rule {
+        Rule { before: Some(ty), ..self }
+    }
+
+    pub fn empty(self) -> Rule {
+        Rule { spacing: Spacing::Empty, ..self }
+    }
+
+    pub fn space(self) -> Rule {
+        Rule { spacing: Spacing::Space, ..self }
+    }
+
+    pub fn newline(self) -> Rule {
+        Rule { spacing: Spacing::Newline, ..self }
+    }
+
+    /// Restricts the rule to the gaps without line breaks, so that a layout, which spans several lines, is kept.
+    pub fn inline(self) -> Rule {
+        Rule { inline: true, ..self }
+    }
+
+    fn matches(&self, parent: Node, left: &[NodeType], right: &[NodeType], multiline: bool) -> bool {
+        !(self.inline && multiline)
+            && self.parent.is_none_or(|ty| parent.ty() == ty)
+            && self.after.is_none_or(|ty| left.contains(&ty))
+            && self.before.is_none_or(|ty| right.contains(&ty))
+    }
+}
+
+#[derive(Debug)]
+pub struct Formatter {
+    rules: Vec<Rule>,
+    indenter: Indenter,
+}
+
+impl Default for Formatter {
+    fn default() -> Formatter {
+        Formatter::new()
+    }
+}
+
+impl Formatter {
+    pub fn new() -> Formatter {
+        Formatter { rules: Vec::new(), indenter: Indenter::new() }
+    }
+
+    /// Adds a rule. If several rules match a gap, the first one wins.
+    pub fn rule(mut self, rule: Rule) -> Formatter {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Line breaks are indented with the `indenter`.
+    pub fn indenter(mut self, indenter: Indenter) -> Formatter {
+        self.indenter = indenter;
+        self
+    }
+
+    /// Whitespace edits for the gaps between tokens, which lie within the `range`, in the document order.
+    pub fn format(&self, file: &File, range: TextRange) -> Vec<Edit> {
+        let mut result = Vec::new();
+        let tokens: Vec<Token> = file.root().tokens().significant().collect();
+        for pair in tokens.windows(2) {
+            let (left, right) = (pair[0].node(), pair[1].node());
+            let gap = TextRange::from_to(left.range().end(), right.range().start());
+            if !gap.is_subrange_of(range) {
+                continue;
+            }
+            let old = file.text().slice(gap).to_string();
+            if !old.chars().all(char::is_whitespace) {
+                continue;
+            }
+            let spacing = match self.spacing(left, right, old.contains('\n')) {
+                Some(spacing) => spacing,
+                None => continue,
+            };
+            let new = match spacing {
+                Spacing::Empty => String::new(),
+                Spacing::Space => " ".to_owned(),
+                Spacing::Newline => {
+                    let breaks = if old.matches('\n').count() > 1 { "\n\n" } else { "\n" };
+                    format!("{}{}", breaks, self.indenter.indent_of(right))
+                }
+            };
+            if old != new {
+                result.push(Edit { delete: gap, insert: new });
+            }
+        }
+        result
+    }
+
+    fn spacing(&self, left: Node, right: Node, multiline: bool) -> Option<Spacing> {
+        let left_ancestors: Vec<Node> = ancestors(left).collect();
+        let parent = ancestors(right).find(|node| left_ancestors.contains(node))?;
+        let left_types: Vec<NodeType> = ancestors(left).take_while(|&node| node != parent).map(|node| node.ty()).collect();
+        let right_types: Vec<NodeType> = ancestors(right).take_while(|&node| node != parent).map(|node| node.ty()).collect();
+        if left_types.contains(&ERROR) || right_types.contains(&ERROR) || ancestors(parent).any(|node| node.ty() == ERROR) {
+            return None;
+        }
+        self.rules.iter()
+            .find(|rule| rule.matches(parent, &left_types, &right_types, multiline))
+            .map(|rule| rule.spacing)
+    }
+}
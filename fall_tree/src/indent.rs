@@ -0,0 +1,143 @@
+//! Indentation, computed from the enclosing nodes.
+//!
+//! A language declares the node types, which indent their children. The first and the last
+//! significant child, usually a pair of braces, stay at the level of the node itself.
+
+use std::iter::successors;
+
+use {File, Node, NodeType, TextRange, TextUnit, Edit};
+use search::{ancestors, find_leaf_at_offset, LeafAtOffset};
+
+#[derive(Clone, Debug)]
+pub struct Indenter {
+    indented: Vec<NodeType>,
+    unit: &'static str,
+}
+
+impl Default for Indenter {
+    fn default() -> Indenter {
+        Indenter::new()
+    }
+}
+
+impl Indenter {
+    pub fn new() -> Indenter {
+        Indenter { indented: Vec::new(), unit: "    " }
+    }
+
+    /// Children of `ty` nodes, except for the first and the last one, are indented.
+    pub fn indent(mut self, ty: NodeType) -> Indenter {
+        self.indented.push(ty);
+        self
+    }
+
+    pub fn unit(mut self, unit: &'static str) -> Indenter {
+        self.unit = unit;
+        self
+    }
+
+    /// The number of indentation units, which precede the `node` if it starts a line.
+    pub fn indent_level(&self, node: Node) -> usize {
+        let mut result = 0;
+        let mut child = node;
+        for parent in ancestors(node).skip(1) {
+            if self.indented.contains(&parent.ty()) && is_inner_child(child) {
+                result += 1;
+            }
+            child = parent;
+        }
+        result
+    }
+
+    /// The indentation of the `node` if it starts a line.
+    pub fn indent_of(&self, node: Node) -> String {
+        self.unit.repeat(self.indent_level(node))
+    }
+
+    /// The indentation of the line, which is inserted by breaking the current line at the `offset`.
+    ///
+    /// If the rest of the line is moved to the new line, it is indented as its first token,
+    /// otherwise as a new child of the nodes which enclose the `offset`.
+    ///
+    /// The indentation is empty if the `offset` is not a char boundary of the text.
+    pub fn indent_for_new_line(&self, file: &File, offset: TextUnit) -> String {
+        let text = file.text().to_cow();
+        let line = match text.get(offset.as_u32() as usize..) {
+            Some(line) => line,
+            None => return String::new(),
+        };
+        let line = &line[..line.find('\n').unwrap_or(line.len())];
+        let indent_len = line.len() - line.trim_start_matches(is_indent).len();
+        if indent_len < line.len() {
+            let first = offset + TextUnit::from_usize(indent_len);
+            if let Some(leaf) = find_leaf_at_offset(file.root(), first).right_biased() {
+                return self.indent_of(leaf);
+            }
+        }
+        let leaf = match find_leaf_at_offset(file.root(), offset).left_biased() {
+            Some(leaf) => leaf,
+            None => return String::new(),
+        };
+        let level = ancestors(leaf)
+            .filter(|node| self.indented.contains(&node.ty()))
+            .filter(|&node| encloses(node, offset))
+            .count();
+        self.unit.repeat(level)
+    }
+
+    /// Edits, which fix the indentation of the lines, which start within the `range`.
+    ///
+    /// Blank lines and lines, which continue a multiline token, like a string, are left as is.
+    pub fn reindent(&self, file: &File, range: TextRange) -> Vec<Edit> {
+        let text = file.text().to_cow();
+        let line_starts = ::std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1));
+        let mut result = Vec::new();
+        for start in line_starts {
+            let offset = TextUnit::from_usize(start);
+            if !range.contains_offset_nonstrict(offset) {
+                continue;
+            }
+            let line = &text[start..];
+            let indent_len = line.len() - line.trim_start_matches(is_indent).len();
+            if line[indent_len..].starts_with('\n') || indent_len == line.len() {
+                continue;
+            }
+            if let LeafAtOffset::Single(leaf) = find_leaf_at_offset(file.root(), offset) {
+                if leaf.range().start() != offset && !leaf.text().to_cow().chars().all(char::is_whitespace) {
+                    continue;
+                }
+            }
+            let first = TextUnit::from_usize(start + indent_len);
+            let leaf = match find_leaf_at_offset(file.root(), first).right_biased() {
+                Some(leaf) => leaf,
+                None => continue,
+            };
+            let indent = self.indent_of(leaf);
+            if line[..indent_len] != indent {
+                result.push(Edit { delete: TextRange::from_to(offset, first), insert: indent });
+            }
+        }
+        result
+    }
+}
+
+fn is_indent(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+/// Whether some significant children of the `node` end before the `offset`, and some start after it.
+fn encloses(node: Node, offset: TextUnit) -> bool {
+    node.children().filter(is_significant).any(|child| child.range().end() <= offset)
+        && node.children().filter(is_significant).any(|child| child.range().start() >= offset)
+}
+
+/// Whether there are significant siblings on both sides of the `node`.
+fn is_inner_child(node: Node) -> bool {
+    successors(node.prev_sibling(), |n| n.prev_sibling()).any(|n| is_significant(&n))
+        && successors(node.next_sibling(), |n| n.next_sibling()).any(|n| is_significant(&n))
+}
+
+fn is_significant(node: &Node) -> bool {
+    !node.language().is_trivia(node.ty()) && !node.range().is_empty()
+}
@@ -0,0 +1,82 @@
+use {Language, NodeType, INode, TextUnit};
+use registry;
+
+/// Declares that the text of `token`s, which are children of `parent` nodes,
+/// is written in another `language`. The language is looked up by name in the
+/// global registry at parse time, tokens of unregistered languages stay opaque.
+///
+/// If the token is quoted, like a string literal, only the text between
+/// the first and the last `"` is injected.
+#[derive(Clone, Copy, Debug)]
+pub struct Injection {
+    pub parent: NodeType,
+    pub token: NodeType,
+    pub language: &'static str,
+}
+
+/// Parses the injected tokens and turns them into composite nodes of the same type. Such node
+/// holds the tree of the embedded language, surrounded by the leaves for the quotes, if any.
+///
+/// Returns the new tree and the embedded languages.
+pub fn inject(lang: &Language, text: &str, root: INode) -> (INode, Vec<Language>) {
+    let mut embedded = Vec::new();
+    if lang.injections().is_empty() {
+        return (root, embedded);
+    }
+    let languages: Vec<Language> = {
+        let registry = registry::global();
+        lang.injections().iter().filter_map(|i| registry.by_name(i.language).cloned()).collect()
+    };
+    let root = rebuild(lang.injections(), text, &root, None, &mut |injection, text, node| {
+        if !node.children().is_empty() {
+            return None;
+        }
+        let lang = languages.iter().find(|l| l.name() == injection.language)?;
+        let (start, end) = match (text.find('"'), text.rfind('"')) {
+            (Some(start), Some(end)) if start < end => (start + 1, end),
+            _ => (0, text.len()),
+        };
+        let file = lang.parse(text[start..end].to_owned());
+        embedded.extend(file.languages().iter().cloned());
+        let mut result = INode::new(node.ty());
+        if start != 0 {
+            result.push_child(INode::new_leaf(node.ty(), TextUnit::from_usize(start)));
+        }
+        result.push_child(file.inode());
+        if end != text.len() {
+            result.push_child(INode::new_leaf(node.ty(), TextUnit::from_usize(text.len() - end)));
+        }
+        Some(result)
+    }).unwrap_or(root);
+    (root, embedded)
+}
+
+/// Turns the injected tokens back into leaves, so that the tree can be reparsed by the host language.
+pub fn strip(lang: &Language, text: &str, root: INode) -> INode {
+    rebuild(lang.injections(), text, &root, None, &mut |_, _, node| {
+        if node.children().is_empty() {
+            None
+        } else {
+            Some(INode::new_leaf(node.ty(), node.len()))
+        }
+    }).unwrap_or(root)
+}
+
+/// Applies `f` to the nodes, which match some injection, returning `None` if nothing has changed.
+fn rebuild<F>(injections: &[Injection], text: &str, node: &INode, parent: Option<NodeType>, f: &mut F) -> Option<INode>
+    where F: FnMut(&Injection, &str, &INode) -> Option<INode>
+{
+    if let Some(injection) = injections.iter().find(|i| Some(i.parent) == parent && i.token == node.ty()) {
+        return f(injection, text, node);
+    }
+    let mut result: Option<INode> = None;
+    let mut offset = 0;
+    for (idx, child) in node.children().iter().enumerate() {
+        let len = child.len().as_u32() as usize;
+        if let Some(child) = rebuild(injections, &text[offset..offset + len], child, Some(node.ty()), f) {
+            result.get_or_insert_with(|| node.clone()).replace_child(idx, child);
+        }
+        offset += len;
+    }
+    result
+}
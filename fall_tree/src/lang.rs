@@ -1,36 +1,111 @@
 use std::sync::Arc;
-use {File, NodeType, NodeTypeInfo, FileStats, INode, TextRange, TextUnit, Edit};
+use {File, NodeType, NodeTypeInfo, FileStats, INode, TextRange, TextUnit, Edit, WHITESPACE, Injection, language_tag};
+use inject;
 
 #[derive(Clone)]
 pub struct Language {
-    imp: Arc<LanguageImpl>
+    imp: Arc<LanguageImpl>,
+    tag: u16,
 }
 
 impl Language {
     pub fn new<I: LanguageImpl>(imp: I) -> Language {
-        Language { imp: Arc::new(imp) }
+        let tag = language_tag(imp.name());
+        Language { imp: Arc::new(imp), tag }
     }
 
     pub fn parse(&self, text: String) -> File {
         let (stats, inode) = self.imp.parse(&text);
-        File::new(self.clone(), text, stats, inode)
+        let (inode, embedded) = inject::inject(self, &text, inode);
+        File::new_mixed(self.clone(), embedded, text, stats, inode)
     }
 
     pub fn reparse(&self, file: &File, edit: &Edit) -> File {
         let before = file.text().slice(TextRange::from_to(TextUnit::zero(), edit.delete.start()));
         let after = file.text().slice(TextRange::from_to(edit.delete.end(), file.text().len()));
         let new_text = before.to_string() + &edit.insert + &after.to_string();
-        let (stats, inode) = self.imp.parse(&new_text);
-        let result = File::new(self.clone(), new_text, stats, inode);
-        return result;
+        // Injected subtrees are not reparsed incrementally, they are stripped and parsed anew.
+        let (stats, inode) = if self.injections().is_empty() {
+            self.imp.reparse(file, edit, &new_text)
+        } else {
+            let text = file.text().to_string();
+            let inode = inject::strip(self, &text, file.inode());
+            let host = File::new(self.clone(), text, file.stats(), inode);
+            self.imp.reparse(&host, edit, &new_text)
+        };
+        let (inode, embedded) = inject::inject(self, &new_text, inode);
+        File::new_mixed(self.clone(), embedded, new_text, stats, inode)
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.imp.name()
+    }
+
+    /// Extensions of the files in this language, without the leading dot.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        self.imp.extensions()
+    }
+
+    /// Changes whenever the grammar changes, so that trees built by different
+    /// versions of the language can be told apart.
+    pub fn version(&self) -> u64 {
+        self.imp.version()
+    }
+
+    /// Tag, which is stored in the upper bits of the language's node types.
+    pub fn tag(&self) -> u16 {
+        self.tag
+    }
+
+    /// Whether `ty` is one of the language's own types. `ERROR` and `WHITESPACE` are not owned by any language.
+    pub fn owns(&self, ty: NodeType) -> bool {
+        ty.language_tag() == self.tag
     }
 
     pub fn node_type_info(&self, ty: NodeType) -> NodeTypeInfo {
         self.imp.node_type_info(ty)
     }
+
+    /// All node types of the language, including `ERROR` and `WHITESPACE`.
+    pub fn node_types(&self) -> &'static [NodeType] {
+        self.imp.node_types()
+    }
+
+    pub fn node_type_by_name(&self, name: &str) -> Option<NodeType> {
+        self.node_types().iter().cloned().find(|&ty| self.node_type_info(ty).name == name)
+    }
+
+    /// Tokens, whose text is parsed with other languages.
+    pub fn injections(&self) -> &'static [Injection] {
+        self.imp.injections()
+    }
+
+    /// Trivia tokens, like whitespace and comments, are skipped by the parser.
+    pub fn is_trivia(&self, ty: NodeType) -> bool {
+        self.imp.is_trivia(ty)
+    }
+}
+
+impl ::std::fmt::Debug for Language {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "Language({})", self.name())
+    }
 }
 
 pub trait LanguageImpl: 'static + Send + Sync {
+    fn name(&self) -> &'static str;
+    fn extensions(&self) -> &'static [&'static str];
+    fn version(&self) -> u64;
     fn parse(&self, text: &str) -> (FileStats, INode);
+    fn reparse(&self, file: &File, edit: &Edit, new_text: &str) -> (FileStats, INode);
+    fn node_types(&self) -> &'static [NodeType];
     fn node_type_info(&self, ty: NodeType) -> NodeTypeInfo;
+
+    fn is_trivia(&self, ty: NodeType) -> bool {
+        ty == WHITESPACE
+    }
+
+    fn injections(&self) -> &'static [Injection] {
+        &[]
+    }
 }
@@ -1,6 +1,8 @@
 extern crate difference;
 extern crate elapsed;
 extern crate serde;
+#[macro_use]
+extern crate serde_json;
 
 mod text;
 mod node_type;
@@ -8,14 +10,27 @@ mod node;
 mod ast;
 mod util;
 mod lang;
+mod line_index;
+mod inject;
 
 pub mod visitor;
 pub mod search;
+pub mod diff;
+pub mod rewrite;
+pub mod tokens;
+pub mod serialize;
+pub mod registry;
+pub mod query;
+pub mod ssr;
+pub mod format;
+pub mod indent;
 pub mod test_util;
 
 pub use text::{Text, TextRange, TextUnit};
-pub use node_type::{NodeType, NodeTypeInfo, ERROR, WHITESPACE};
+pub use node_type::{NodeType, NodeTypeInfo, ERROR, WHITESPACE, language_tag};
 pub use node::{File, Node, FileStats, INode, Edit};
 pub use lang::{Language, LanguageImpl};
+pub use inject::Injection;
+pub use line_index::{LineIndex, LineCol, ColumnUnit};
 pub use ast::{AstNode, AstChildren, AstClass, AstClassChildren};
-pub use util::{dump_file, dump_file_ws, walk_tree};
+pub use util::{dump_file, dump_file_ws, walk_tree, Preorder, WalkEvent};
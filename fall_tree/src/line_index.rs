@@ -0,0 +1,154 @@
+use TextUnit;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineCol {
+    /// Zero-based line number.
+    pub line: u32,
+    /// Zero-based column, measured in some `ColumnUnit`s.
+    pub col: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnUnit {
+    Utf8,
+    Utf16,
+    Char,
+}
+
+/// Converts between offsets and line/column pairs.
+///
+/// Lines are separated by `\n` or `\r\n`. The line terminator does not count as
+/// a part of the line, so the offset between `\r` and `\n` is mapped to the end of the line.
+#[derive(Debug)]
+pub struct LineIndex {
+    /// Start of each line, the first one is always zero.
+    line_starts: Vec<TextUnit>,
+    /// Length of each line without the terminator.
+    line_lens: Vec<TextUnit>,
+    /// All non-ASCII chars, sorted by offset.
+    wide_chars: Vec<WideChar>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct WideChar {
+    start: TextUnit,
+    len_utf8: u32,
+}
+
+impl WideChar {
+    fn len(&self, unit: ColumnUnit) -> u32 {
+        match unit {
+            ColumnUnit::Utf8 => self.len_utf8,
+            ColumnUnit::Utf16 => if self.len_utf8 == 4 { 2 } else { 1 },
+            ColumnUnit::Char => 1,
+        }
+    }
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> LineIndex {
+        let mut line_starts = vec![TextUnit::zero()];
+        let mut line_lens = Vec::new();
+        let mut wide_chars = Vec::new();
+        for (offset, c) in text.char_indices() {
+            if c == '\n' {
+                let end = if text[..offset].ends_with('\r') { offset - 1 } else { offset };
+                line_lens.push(TextUnit::from_usize(end) - *line_starts.last().unwrap());
+                line_starts.push(TextUnit::from_usize(offset + 1));
+            } else if !c.is_ascii() {
+                wide_chars.push(WideChar { start: TextUnit::from_usize(offset), len_utf8: c.len_utf8() as u32 });
+            }
+        }
+        line_lens.push(TextUnit::measure(text) - *line_starts.last().unwrap());
+        LineIndex { line_starts, line_lens, wide_chars }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    pub fn line_start(&self, line: u32) -> TextUnit {
+        self.line_starts[line as usize]
+    }
+
+    pub fn line_col(&self, offset: TextUnit, unit: ColumnUnit) -> LineCol {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let start = self.line_starts[line];
+        let col = ::std::cmp::min(offset - start, self.line_lens[line]).as_u32();
+        let col = col - self.wide_chars(line).iter()
+            .take_while(|wc| wc.start < offset)
+            .map(|wc| wc.len_utf8 - wc.len(unit))
+            .sum::<u32>();
+        LineCol { line: line as u32, col }
+    }
+
+    /// Inverse of `line_col`. Columns past the end of the line are clamped,
+    /// and columns inside a char, like between the halves of a surrogate pair, point to its start.
+    pub fn offset(&self, line_col: LineCol, unit: ColumnUnit) -> TextUnit {
+        let line = line_col.line as usize;
+        let start = self.line_starts[line];
+        let mut shift = 0;
+        for wc in self.wide_chars(line) {
+            let col = (wc.start - start).as_u32() - shift;
+            if col >= line_col.col {
+                break;
+            }
+            if line_col.col < col + wc.len(unit) {
+                return wc.start;
+            }
+            shift += wc.len_utf8 - wc.len(unit);
+        }
+        start + ::std::cmp::min(line_col.col + shift, self.line_lens[line].as_u32())
+    }
+
+    fn wide_chars(&self, line: usize) -> &[WideChar] {
+        let start = self.line_starts[line];
+        let end = start + self.line_lens[line];
+        let lo = self.wide_chars.binary_search_by_key(&start, |wc| wc.start).unwrap_or_else(|idx| idx);
+        let hi = self.wide_chars.binary_search_by_key(&end, |wc| wc.start).unwrap_or_else(|idx| idx);
+        &self.wide_chars[lo..hi]
+    }
+}
+
+#[test]
+fn line_col_ascii_and_crlf() {
+    let index = LineIndex::new("ab\r\ncd\n\nef");
+    assert_eq!(index.line_count(), 4);
+    let check = |offset: u32, line: u32, col: u32| {
+        let line_col = LineCol { line, col };
+        assert_eq!(index.line_col(TextUnit::from_usize(offset as usize), ColumnUnit::Utf8), line_col);
+    };
+    check(0, 0, 0);
+    check(2, 0, 2);
+    check(3, 0, 2);
+    check(4, 1, 0);
+    check(6, 1, 2);
+    check(7, 2, 0);
+    check(8, 3, 0);
+    check(10, 3, 2);
+
+    assert_eq!(index.offset(LineCol { line: 1, col: 1 }, ColumnUnit::Utf8), TextUnit::from_usize(5));
+    assert_eq!(index.offset(LineCol { line: 0, col: 92 }, ColumnUnit::Utf8), TextUnit::from_usize(2));
+}
+
+#[test]
+fn line_col_wide_chars() {
+    // 'λ' is two bytes and one UTF-16 unit, '𝔸' is four bytes and two UTF-16 units.
+    let text = "x\nλ𝔸y\n";
+    let index = LineIndex::new(text);
+    let y = TextUnit::from_usize(text.find('y').unwrap());
+    let expected = [(ColumnUnit::Utf8, 6), (ColumnUnit::Utf16, 3), (ColumnUnit::Char, 2)];
+    for &(unit, col) in expected.iter() {
+        let line_col = LineCol { line: 1, col };
+        assert_eq!(index.line_col(y, unit), line_col);
+        assert_eq!(index.offset(line_col, unit), y);
+    }
+    let a = TextUnit::from_usize(text.find('𝔸').unwrap());
+    assert_eq!(index.line_col(a, ColumnUnit::Utf16), LineCol { line: 1, col: 1 });
+    assert_eq!(index.offset(LineCol { line: 1, col: 1 }, ColumnUnit::Char), a);
+    assert_eq!(index.offset(LineCol { line: 1, col: 2 }, ColumnUnit::Utf16), a);
+    assert_eq!(index.offset(LineCol { line: 1, col: 4 }, ColumnUnit::Utf8), a);
+}
@@ -0,0 +1,59 @@
+use {Node, NodeType, Language, TextRange, ERROR};
+use super::{File, INode};
+
+/// Syntax errors of the file, in the document order. Errors inside other errors are not reported.
+///
+/// An empty error node means that something is missing, and its message lists the expected
+/// tokens or nodes. A non-empty error node holds unexpected tokens, which the parser has skipped.
+/// Error leaves are the characters the lexer could not recognize.
+pub fn file_errors(file: &File) -> Vec<(TextRange, String)> {
+    let mut result = Vec::new();
+    go(file.root(), &file.inode(), &mut result);
+    return result;
+
+    fn go(node: Node, inode: &INode, acc: &mut Vec<(TextRange, String)>) {
+        if node.ty() == ERROR {
+            acc.push((node.range(), message(node, inode.expected())));
+            return;
+        }
+        for (child, ichild) in node.children().zip(inode.children()) {
+            go(child, ichild, acc);
+        }
+    }
+}
+
+fn message(node: Node, expected: &[NodeType]) -> String {
+    let lang = node.language();
+    let first_leaf = {
+        let mut leaf = node;
+        while let Some(child) = leaf.first_child() {
+            leaf = child;
+        }
+        leaf
+    };
+    if first_leaf == node && node.range().is_empty() {
+        if expected.is_empty() {
+            return "syntax error".to_owned();
+        }
+        return format!("{} expected", one_of(lang, expected));
+    }
+    let unexpected = if first_leaf.ty() == ERROR {
+        format!("{:?}", first_leaf.text().to_string())
+    } else {
+        lang.node_type_info(first_leaf.ty()).display_name()
+    };
+    if expected.is_empty() {
+        format!("unexpected {}", unexpected)
+    } else {
+        format!("unexpected {}, expected {}", unexpected, one_of(lang, expected))
+    }
+}
+
+/// `'(' or IDENT`, `'(', IDENT or '['`.
+fn one_of(lang: &Language, types: &[NodeType]) -> String {
+    let names: Vec<String> = types.iter().map(|&ty| lang.node_type_info(ty).display_name()).collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        _ => names.join(""),
+    }
+}
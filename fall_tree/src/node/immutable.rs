@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use ::{NodeType, TextUnit};
+use ::{NodeType, TextUnit, ERROR};
 
 #[derive(Clone, Debug)]
 pub struct INode {
@@ -12,6 +12,8 @@ struct Inner {
     pub ty: NodeType,
     pub children: Vec<INode>,
     pub len: TextUnit,
+    /// For error nodes, the tokens or nodes the parser expected to see.
+    pub expected: Vec<NodeType>,
 }
 
 impl INode {
@@ -21,6 +23,18 @@ impl INode {
                 ty: ty,
                 children: Vec::new(),
                 len: TextUnit::zero(),
+                expected: Vec::new(),
+            })
+        }
+    }
+
+    pub fn new_error(expected: Vec<NodeType>) -> INode {
+        INode {
+            inner: Arc::new(Inner {
+                ty: ERROR,
+                children: Vec::new(),
+                len: TextUnit::zero(),
+                expected: expected,
             })
         }
     }
@@ -31,6 +45,7 @@ impl INode {
                 ty: ty,
                 children: Vec::new(),
                 len: len,
+                expected: Vec::new(),
             })
         }
     }
@@ -44,6 +59,12 @@ impl INode {
         inner.children.push(child);
     }
 
+    pub fn replace_child(&mut self, idx: usize, child: INode) {
+        let inner = Arc::make_mut(&mut self.inner);
+        inner.len = inner.len - inner.children[idx].len() + child.len();
+        inner.children[idx] = child;
+    }
+
     pub fn ty(&self) -> NodeType {
         self.inner.ty
     }
@@ -52,6 +73,10 @@ impl INode {
         self.inner.len
     }
 
+    pub fn expected(&self) -> &[NodeType] {
+        &self.inner.expected
+    }
+
     pub fn children(&self) -> &[INode] {
         &self.inner.children
     }
@@ -5,7 +5,8 @@ use super::{Node, FileStats};
 use super::immutable::INode;
 
 pub struct FileImpl {
-    pub lang: Language,
+    /// The language of the file goes first, followed by the languages of the embedded subtrees.
+    languages: Vec<Language>,
     stats: FileStats,
     text: String,
     root: NodeId,
@@ -13,6 +14,14 @@ pub struct FileImpl {
 }
 
 impl FileImpl {
+    pub fn language(&self) -> &Language {
+        &self.languages[0]
+    }
+
+    pub fn languages(&self) -> &[Language] {
+        &self.languages
+    }
+
     pub fn root(&self) -> Node {
         Node(NodeImpl { id: self.root, file: self })
     }
@@ -40,6 +49,13 @@ impl<'f> ::std::cmp::PartialEq for NodeImpl<'f> {
 
 impl<'f> ::std::cmp::Eq for NodeImpl<'f> {}
 
+impl<'f> ::std::hash::Hash for NodeImpl<'f> {
+    fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+        (self.file as *const FileImpl).hash(state);
+        self.id.hash(state);
+    }
+}
+
 impl<'f> NodeImpl<'f> {
     pub fn ty(&self) -> NodeType {
         self.data().ty
@@ -54,15 +70,59 @@ impl<'f> NodeImpl<'f> {
     }
 
     pub fn parent(&self) -> Option<Node<'f>> {
-        self.data().parent.map(|id| Node(NodeImpl { id: id, file: self.file }))
+        self.data().parent.map(|id| self.node(id))
     }
 
     pub fn children(&self) -> NodeChildren<'f> {
         NodeChildren { file: self.file, inner: self.data().children.iter() }
     }
 
+    pub fn first_child(&self) -> Option<Node<'f>> {
+        self.data().children.first().map(|&id| self.node(id))
+    }
+
+    pub fn last_child(&self) -> Option<Node<'f>> {
+        self.data().children.last().map(|&id| self.node(id))
+    }
+
+    pub fn index_in_parent(&self) -> usize {
+        self.data().index_in_parent as usize
+    }
+
+    pub fn next_sibling(&self) -> Option<Node<'f>> {
+        let siblings = &self.file[self.data().parent?].children;
+        siblings.get(self.index_in_parent() + 1).map(|&id| self.node(id))
+    }
+
+    pub fn prev_sibling(&self) -> Option<Node<'f>> {
+        let siblings = &self.file[self.data().parent?].children;
+        let idx = self.index_in_parent().checked_sub(1)?;
+        Some(self.node(siblings[idx]))
+    }
+
+    /// The language which owns the type of this node, or of the closest
+    /// ancestor for the types which are shared by all languages.
+    pub fn language(&self) -> &'f Language {
+        let mut node = *self;
+        loop {
+            let tag = node.ty().language_tag();
+            if tag != 0 {
+                return self.file.languages.iter().find(|lang| lang.tag() == tag)
+                    .unwrap_or_else(|| panic!("node type of an unknown language: {:?}", node.ty()));
+            }
+            match node.data().parent {
+                Some(id) => node = NodeImpl { id, file: self.file },
+                None => return self.file.language(),
+            }
+        }
+    }
+
+    fn node(&self, id: NodeId) -> Node<'f> {
+        Node(NodeImpl { id, file: self.file })
+    }
+
     pub fn debug(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-        write!(f, "Node({})", self.file.lang.node_type_info(self.ty()).name)
+        write!(f, "Node({})", self.language().node_type_info(self.ty()).name)
     }
 
     fn data(&self) -> &'f NodeData {
@@ -83,7 +143,7 @@ impl<'f> Iterator for NodeChildren<'f> {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct NodeId(u32);
 
 impl Index<NodeId> for FileImpl {
@@ -97,16 +157,17 @@ pub struct NodeData {
     ty: NodeType,
     parent: Option<NodeId>,
     children: Vec<NodeId>,
+    index_in_parent: u32,
     range: TextRange,
 }
 
 
-pub fn new_file(lang: Language, text: String, stats: FileStats, node: &INode) -> FileImpl {
+pub fn new_file(languages: Vec<Language>, text: String, stats: FileStats, node: &INode) -> FileImpl {
     let mut nodes = Vec::new();
     go(TextUnit::zero(), node, &mut nodes);
 
     return FileImpl {
-        lang: lang,
+        languages: languages,
         stats: stats,
         text: text,
         root: NodeId(0),
@@ -119,14 +180,16 @@ pub fn new_file(lang: Language, text: String, stats: FileStats, node: &INode) ->
             ty: node.ty(),
             parent: None,
             children: Vec::new(),
+            index_in_parent: 0,
             range: TextRange::from_to(range_start, range_start + node.len()),
         });
         let mut range_start = range_start;
-        for child in node.children() {
+        for (idx, child) in node.children().iter().enumerate() {
             let child_idx = nodes.len();
             nodes[my_idx].children.push(NodeId(child_idx as u32));
             go(range_start, child, nodes);
             nodes[child_idx].parent = Some(NodeId(my_idx as u32));
+            nodes[child_idx].index_in_parent = idx as u32;
             range_start += child.len();
         }
     }
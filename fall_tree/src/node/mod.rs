@@ -1,12 +1,16 @@
 use std::time::Duration;
-use {Text, TextRange, NodeType, Language};
+use std::sync::OnceLock;
+use {Text, TextRange, NodeType, Language, LineIndex};
+use tokens::Tokens;
 
 mod imp;
 mod immutable;
+mod errors;
 
 pub use self::imp::NodeChildren;
 pub use self::immutable::INode;
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Edit {
     pub delete: TextRange,
     pub insert: String,
@@ -15,18 +19,38 @@ pub struct Edit {
 pub struct File {
     imp: imp::FileImpl,
     inode: INode,
+    line_index: OnceLock<LineIndex>,
 }
 
 impl File {
     pub fn new(lang: Language, text: String, stats: FileStats, node: INode) -> File {
+        File::new_mixed(lang, Vec::new(), text, stats, node)
+    }
+
+    /// Creates a file, whose tree also contains nodes of the `embedded` languages.
+    pub fn new_mixed(lang: Language, embedded: Vec<Language>, text: String, stats: FileStats, node: INode) -> File {
+        let mut languages = vec![lang];
+        for lang in embedded {
+            if let Some(other) = languages.iter().find(|other| other.tag() == lang.tag()) {
+                assert_eq!(other.name(), lang.name(), "languages with the same tag");
+                continue;
+            }
+            languages.push(lang);
+        }
         File {
-            imp: imp::new_file(lang, text, stats, &node),
+            imp: imp::new_file(languages, text, stats, &node),
             inode: node,
+            line_index: OnceLock::new(),
         }
     }
 
     pub fn language(&self) -> &Language {
-        &self.imp.lang
+        self.imp.language()
+    }
+
+    /// The language of the file, followed by the languages of the embedded subtrees.
+    pub fn languages(&self) -> &[Language] {
+        self.imp.languages()
     }
 
     pub fn root(&self) -> Node {
@@ -45,12 +69,26 @@ impl File {
         self.inode.clone()
     }
 
+    pub fn tokens(&self) -> Tokens {
+        self.root().tokens()
+    }
+
+    /// Line index of the text, computed on the first call.
+    pub fn line_index(&self) -> &LineIndex {
+        self.line_index.get_or_init(|| LineIndex::new(&self.text().to_cow()))
+    }
+
+    /// Syntax errors, as ranges with messages like `'(' expected` or `unexpected '}'`.
+    pub fn errors(&self) -> Vec<(TextRange, String)> {
+        errors::file_errors(self)
+    }
+
     pub fn edit(&self, edit: &Edit) -> File {
         self.language().reparse(self, edit)
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Node<'f>(imp::NodeImpl<'f>);
 
 impl<'f> ::std::fmt::Debug for Node<'f> {
@@ -79,6 +117,68 @@ impl<'f> Node<'f> {
     pub fn children(&self) -> NodeChildren<'f> {
         self.0.children()
     }
+
+    pub fn first_child(&self) -> Option<Node<'f>> {
+        self.0.first_child()
+    }
+
+    pub fn last_child(&self) -> Option<Node<'f>> {
+        self.0.last_child()
+    }
+
+    /// Index of this node among the children of its parent, zero for the root.
+    pub fn index_in_parent(&self) -> usize {
+        self.0.index_in_parent()
+    }
+
+    pub fn next_sibling(&self) -> Option<Node<'f>> {
+        self.0.next_sibling()
+    }
+
+    pub fn prev_sibling(&self) -> Option<Node<'f>> {
+        self.0.prev_sibling()
+    }
+
+    /// The language this node belongs to. In a file, which mixes several languages,
+    /// this is not necessarily the language of the file.
+    pub fn language(&self) -> &'f Language {
+        self.0.language()
+    }
+
+    /// Non-empty leaves of this node, including trivia.
+    pub fn tokens(&self) -> Tokens<'f> {
+        Tokens::new(*self)
+    }
+
+    /// The closest leaf which follows this node, possibly in a different subtree.
+    pub fn next_leaf(&self) -> Option<Node<'f>> {
+        let mut node = *self;
+        let mut next = loop {
+            match node.next_sibling() {
+                Some(sibling) => break sibling,
+                None => node = node.parent()?,
+            }
+        };
+        while let Some(child) = next.first_child() {
+            next = child;
+        }
+        Some(next)
+    }
+
+    /// The closest leaf which precedes this node, possibly in a different subtree.
+    pub fn prev_leaf(&self) -> Option<Node<'f>> {
+        let mut node = *self;
+        let mut prev = loop {
+            match node.prev_sibling() {
+                Some(sibling) => break sibling,
+                None => node = node.parent()?,
+            }
+        };
+        while let Some(child) = prev.last_child() {
+            prev = child;
+        }
+        Some(prev)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -1,10 +1,45 @@
+/// The upper 16 bits of a node type hold the tag of the language the type belongs to,
+/// so that types of different languages never coincide and can be mixed in one tree.
+/// `ERROR` and `WHITESPACE` have a zero tag and are shared by all languages.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct NodeType(pub u32);
 
 pub const ERROR: NodeType = NodeType(0);
 pub const WHITESPACE: NodeType = NodeType(1);
 
-#[derive(Clone, Copy)]
+impl NodeType {
+    pub fn language_tag(self) -> u16 {
+        (self.0 >> 16) as u16
+    }
+}
+
+/// Tag of the language with the given name, derived from the hash of the name. Never zero.
+pub fn language_tag(language_name: &str) -> u16 {
+    let hash = language_name.bytes().fold(0x811c9dc5u32, |hash, b| (hash ^ b as u32).wrapping_mul(0x01000193));
+    ((hash ^ (hash >> 16)) % 0xffff) as u16 + 1
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct NodeTypeInfo {
-    pub name: &'static str
+    pub name: &'static str,
+    /// Nodes of token types are produced by the lexer and have no children.
+    pub is_token: bool,
+    /// A token, which is a fixed word, like `fn`.
+    pub is_keyword: bool,
+    /// A token for a literal value, like a number or a string, marked with `#[literal]` in the grammar.
+    pub is_literal: bool,
+    /// Text of a token, which is matched by a fixed string, like `{` for `LBRACE`.
+    pub text: Option<&'static str>,
+    /// Names of the AST classes this type belongs to.
+    pub classes: &'static [&'static str],
+}
+
+impl NodeTypeInfo {
+    /// Human readable name, suitable for error messages: `'{'` instead of `LBRACE`.
+    pub fn display_name(&self) -> String {
+        match self.text {
+            Some(text) => format!("'{}'", text),
+            None => self.name.to_owned(),
+        }
+    }
 }
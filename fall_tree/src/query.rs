@@ -0,0 +1,282 @@
+//! Patterns over the syntax tree, written in terms of node type names:
+//!
+//! ```text
+//! ; functions together with their statements
+//! (FN_DEF (IDENT) @name (BLOCK_EXPR (STMT)* @stmt))
+//! ```
+//!
+//! A pattern matches a node by its type name, `_` matches any node. Child patterns match
+//! the children of the node in order, but not necessarily adjacent ones. Trivia are never matched.
+//! A child pattern may be followed by a quantifier, `*`, `+` or `?`, and by a `@capture`.
+//! A query may consist of several patterns, and `;` starts a comment.
+
+use std::fmt;
+use std::collections::HashSet;
+
+use {Node, Preorder, WalkEvent};
+
+#[derive(Debug)]
+pub struct Query {
+    patterns: Vec<Pattern>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Error {
+    /// Byte offset in the source of the query.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.offset)
+    }
+}
+
+impl ::std::error::Error for Error {}
+
+#[derive(Debug)]
+pub struct QueryMatch<'f> {
+    /// Index of the matched pattern in the query.
+    pub pattern: usize,
+    pub node: Node<'f>,
+    /// Captured nodes in the document order, a quantified pattern can capture several nodes.
+    pub captures: Vec<(String, Node<'f>)>,
+}
+
+impl<'f> QueryMatch<'f> {
+    /// The first node captured with `name`.
+    pub fn get(&self, name: &str) -> Option<Node<'f>> {
+        self.captures.iter().find(|&(n, _)| n == name).map(|&(_, node)| node)
+    }
+}
+
+#[derive(Debug)]
+struct Pattern {
+    /// `None` for the `_` wildcard.
+    name: Option<String>,
+    children: Vec<Pattern>,
+    quantifier: Quantifier,
+    capture: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quantifier {
+    One,
+    Opt,
+    Star,
+    Plus,
+}
+
+impl Query {
+    pub fn new(source: &str) -> Result<Query, Error> {
+        let mut parser = QueryParser { source, pos: 0 };
+        let mut patterns = Vec::new();
+        while parser.peek().is_some() {
+            let pattern = parser.pattern()?;
+            if pattern.quantifier != Quantifier::One {
+                return Err(parser.error("quantifiers are not allowed on top-level patterns"));
+            }
+            patterns.push(pattern);
+        }
+        if patterns.is_empty() {
+            return Err(parser.error("empty query"));
+        }
+        Ok(Query { patterns })
+    }
+
+    /// All matches in the subtree of `root`, in the document order.
+    pub fn matches<'f>(&self, root: Node<'f>) -> Vec<QueryMatch<'f>> {
+        Preorder::new(root)
+            .filter_map(|event| match event {
+                WalkEvent::Enter(node) => Some(node),
+                WalkEvent::Leave(_) => None,
+            })
+            .flat_map(|node| self.match_node(node))
+            .collect()
+    }
+
+    /// Matches of the patterns at exactly this `node`.
+    pub fn match_node<'f>(&self, node: Node<'f>) -> Vec<QueryMatch<'f>> {
+        self.patterns.iter().enumerate()
+            .filter_map(|(idx, pattern)| {
+                let mut captures = Vec::new();
+                if match_pattern(pattern, node, &mut captures) {
+                    Some(QueryMatch { pattern: idx, node, captures })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+fn match_pattern<'f>(pattern: &Pattern, node: Node<'f>, captures: &mut Vec<(String, Node<'f>)>) -> bool {
+    if let Some(ref name) = pattern.name {
+        if node.language().node_type_info(node.ty()).name != name {
+            return false;
+        }
+    }
+    let n_captures = captures.len();
+    if let Some(ref capture) = pattern.capture {
+        captures.push((capture.clone(), node));
+    }
+    if pattern.children.is_empty() {
+        return true;
+    }
+    let children: Vec<Node> = node.children()
+        .filter(|child| !child.language().is_trivia(child.ty()))
+        .collect();
+    if match_children(&pattern.children, &children, captures) {
+        return true;
+    }
+    captures.truncate(n_captures);
+    false
+}
+
+/// Matches `patterns` against a subsequence of `children`, backtracking if necessary.
+/// Quantified patterns are greedy.
+fn match_children<'f>(patterns: &[Pattern], children: &[Node<'f>], captures: &mut Vec<(String, Node<'f>)>) -> bool {
+    let mut matcher = ChildrenMatcher { patterns, children, failed: HashSet::new() };
+    matcher.match_children(0, 0, captures)
+}
+
+/// Remembers the states, which are known to fail, so that backtracking is polynomial.
+/// Whether a state matches doesn't depend on the captures made before it.
+struct ChildrenMatcher<'p, 'n, 'f: 'n> {
+    patterns: &'p [Pattern],
+    children: &'n [Node<'f>],
+    /// Pattern index, child index and whether the pattern is being repeated.
+    failed: HashSet<(usize, usize, bool)>,
+}
+
+impl<'p, 'n, 'f> ChildrenMatcher<'p, 'n, 'f> {
+    /// Matches patterns starting from `pat` against children starting from `child`.
+    fn match_children(&mut self, pat: usize, child: usize, captures: &mut Vec<(String, Node<'f>)>) -> bool {
+        let pattern = match self.patterns.get(pat) {
+            Some(it) => it,
+            None => return true,
+        };
+        if self.failed.contains(&(pat, child, false)) {
+            return false;
+        }
+        let n_captures = captures.len();
+        for idx in child..self.children.len() {
+            if !match_pattern(pattern, self.children[idx], captures) {
+                continue;
+            }
+            let matched = match pattern.quantifier {
+                Quantifier::One | Quantifier::Opt => self.match_children(pat + 1, idx + 1, captures),
+                Quantifier::Star | Quantifier::Plus => self.match_repetition(pat, idx + 1, captures),
+            };
+            if matched {
+                return true;
+            }
+            captures.truncate(n_captures);
+        }
+        let matched = match pattern.quantifier {
+            Quantifier::Opt | Quantifier::Star => self.match_children(pat + 1, child, captures),
+            Quantifier::One | Quantifier::Plus => false,
+        };
+        if !matched {
+            self.failed.insert((pat, child, false));
+        }
+        matched
+    }
+
+    /// Matches more repetitions of the pattern `pat`, followed by the rest of the patterns.
+    fn match_repetition(&mut self, pat: usize, child: usize, captures: &mut Vec<(String, Node<'f>)>) -> bool {
+        if self.failed.contains(&(pat, child, true)) {
+            return false;
+        }
+        let pattern = &self.patterns[pat];
+        let n_captures = captures.len();
+        for idx in child..self.children.len() {
+            if match_pattern(pattern, self.children[idx], captures) {
+                if self.match_repetition(pat, idx + 1, captures) {
+                    return true;
+                }
+                captures.truncate(n_captures);
+            }
+        }
+        let matched = self.match_children(pat + 1, child, captures);
+        if !matched {
+            self.failed.insert((pat, child, true));
+        }
+        matched
+    }
+}
+
+struct QueryParser<'s> {
+    source: &'s str,
+    pos: usize,
+}
+
+impl<'s> QueryParser<'s> {
+    fn pattern(&mut self) -> Result<Pattern, Error> {
+        self.expect('(')?;
+        let name = self.ident().ok_or_else(|| self.error("expected a node type name"))?;
+        let name = if name == "_" { None } else { Some(name.to_owned()) };
+        let mut children = Vec::new();
+        loop {
+            match self.peek() {
+                Some(')') => break,
+                Some('(') => children.push(self.pattern()?),
+                _ => return Err(self.error("expected `(` or `)`")),
+            }
+        }
+        self.expect(')')?;
+        let quantifier = match self.peek() {
+            Some('*') => Quantifier::Star,
+            Some('+') => Quantifier::Plus,
+            Some('?') => Quantifier::Opt,
+            _ => Quantifier::One,
+        };
+        if quantifier != Quantifier::One {
+            self.pos += 1;
+        }
+        let capture = if self.peek() == Some('@') {
+            self.pos += 1;
+            Some(self.ident().ok_or_else(|| self.error("expected a capture name"))?.to_owned())
+        } else {
+            None
+        };
+        Ok(Pattern { name, children, quantifier, capture })
+    }
+
+    fn ident(&mut self) -> Option<&'s str> {
+        self.peek();
+        let rest = &self.source[self.pos..];
+        let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        if len == 0 {
+            return None;
+        }
+        self.pos += len;
+        Some(&rest[..len])
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("expected `{}`", c)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Skips whitespace and comments and returns the next char.
+    fn peek(&mut self) -> Option<char> {
+        loop {
+            let rest = &self.source[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if !trimmed.starts_with(';') {
+                return trimmed.chars().next();
+            }
+            self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error { offset: self.pos, message: message.to_owned() }
+    }
+}
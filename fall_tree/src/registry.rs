@@ -0,0 +1,60 @@
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use Language;
+
+/// Maps language names and file extensions to languages.
+#[derive(Default)]
+pub struct Registry {
+    languages: Vec<Language>,
+}
+
+impl Registry {
+    pub const fn new() -> Registry {
+        Registry { languages: Vec::new() }
+    }
+
+    /// Registers `lang`, replacing the previously registered language with the same name.
+    ///
+    /// Panics if a language with a different name has the same tag,
+    /// because trees of such languages can't be mixed.
+    pub fn register(&mut self, lang: Language) {
+        self.languages.retain(|l| l.name() != lang.name());
+        if let Some(other) = self.languages.iter().find(|l| l.tag() == lang.tag()) {
+            panic!("languages {} and {} have the same tag {}", other.name(), lang.name(), lang.tag());
+        }
+        self.languages.push(lang);
+    }
+
+    pub fn by_tag(&self, tag: u16) -> Option<&Language> {
+        self.languages.iter().find(|l| l.tag() == tag)
+    }
+
+    pub fn languages(&self) -> &[Language] {
+        &self.languages
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Language> {
+        self.languages.iter().find(|l| l.name() == name)
+    }
+
+    /// Finds a language by the file extension, without the leading dot.
+    /// If several languages claim the extension, the last registered wins.
+    pub fn by_extension(&self, extension: &str) -> Option<&Language> {
+        self.languages.iter().rev().find(|l| l.extensions().contains(&extension))
+    }
+
+    pub fn for_path(&self, path: &Path) -> Option<&Language> {
+        let extension = path.extension()?.to_str()?;
+        self.by_extension(extension)
+    }
+}
+
+static GLOBAL: Mutex<Registry> = Mutex::new(Registry::new());
+
+/// The process-wide registry, which generated languages register themselves into.
+///
+/// Languages with injections consult it while parsing, so don't parse while holding the lock.
+pub fn global() -> MutexGuard<'static, Registry> {
+    GLOBAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,247 @@
+use std::collections::{HashMap, HashSet};
+
+use {File, Node, NodeType, INode, Edit, Language, FileStats, TextRange, TextUnit};
+use search::ancestors;
+use util::{Preorder, WalkEvent};
+
+/// A detached subtree together with its text, which can be spliced into a tree.
+#[derive(Clone, Debug)]
+pub struct Fragment {
+    inode: INode,
+    text: String,
+    /// Languages of the nodes of the fragment, so that it can be inserted into a tree of a different language.
+    languages: Vec<Language>,
+}
+
+impl Fragment {
+    /// Copies the subtree of `node`.
+    pub fn from_node(node: Node) -> Fragment {
+        let mut languages = vec![node.language().clone()];
+        let inode = go(node, &mut languages);
+        return Fragment { inode, text: node.text().to_string(), languages };
+
+        fn go(node: Node, languages: &mut Vec<Language>) -> INode {
+            let tag = node.ty().language_tag();
+            if tag != 0 && !languages.iter().any(|lang| lang.tag() == tag) {
+                languages.push(node.language().clone());
+            }
+            if node.children().next().is_none() {
+                return INode::new_leaf(node.ty(), node.range().len());
+            }
+            let mut result = INode::new(node.ty());
+            for child in node.children() {
+                result.push_child(go(child, languages));
+            }
+            result
+        }
+    }
+
+    /// Parses `text` and copies the first node of type `ty`.
+    ///
+    /// Snippets for nodes which can't be parsed standalone should be wrapped
+    /// into the necessary context: `Fragment::parse(&LANG, "fn f() { 1 + 1 }", EXPR)`.
+    pub fn parse(lang: &Language, text: &str, ty: NodeType) -> Option<Fragment> {
+        let file = lang.parse(text.to_owned());
+        let node = Preorder::new(file.root()).find(|&event| match event {
+            WalkEvent::Enter(node) => node.ty() == ty,
+            WalkEvent::Leave(_) => false,
+        });
+        match node {
+            Some(WalkEvent::Enter(node)) => Some(Fragment::from_node(node)),
+            _ => None,
+        }
+    }
+
+    /// A single token. The type should belong to one of the languages of the file it is inserted into.
+    pub fn token(ty: NodeType, text: &str) -> Fragment {
+        Fragment { inode: INode::new_leaf(ty, TextUnit::measure(text)), text: text.to_owned(), languages: Vec::new() }
+    }
+
+    pub fn ty(&self) -> NodeType {
+        self.inode.ty()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// Collects modifications of the `file`'s tree and applies them all at once.
+///
+/// Nodes are addressed in terms of the original tree, so modifications do not
+/// invalidate each other, unless they touch the same subtree.
+pub struct Rewriter<'f> {
+    file: &'f File,
+    replacements: HashMap<Node<'f>, Option<Fragment>>,
+    insertions: HashMap<Node<'f>, Vec<(usize, Fragment)>>,
+}
+
+impl<'f> Rewriter<'f> {
+    pub fn new(file: &'f File) -> Rewriter<'f> {
+        Rewriter { file, replacements: HashMap::new(), insertions: HashMap::new() }
+    }
+
+    pub fn replace(&mut self, node: Node<'f>, fragment: Fragment) {
+        self.set_replacement(node, Some(fragment))
+    }
+
+    pub fn delete(&mut self, node: Node<'f>) {
+        self.set_replacement(node, None)
+    }
+
+    /// Inserts `fragment` as a child of `parent`, before the child with index `idx`.
+    /// Fragments inserted at the same place are kept in the order of insertion.
+    pub fn insert_child(&mut self, parent: Node<'f>, idx: usize, fragment: Fragment) {
+        let n_children = parent.children().count();
+        assert!(idx <= n_children, "child index out of bounds: {} > {}", idx, n_children);
+        assert!(n_children != 0 || parent.range().is_empty(), "can't insert into a token: {:?}", parent);
+        self.insertions.entry(parent).or_default().push((idx, fragment))
+    }
+
+    pub fn insert_before(&mut self, anchor: Node<'f>, fragment: Fragment) {
+        let (parent, idx) = position(anchor);
+        self.insert_child(parent, idx, fragment)
+    }
+
+    pub fn insert_after(&mut self, anchor: Node<'f>, fragment: Fragment) {
+        let (parent, idx) = position(anchor);
+        self.insert_child(parent, idx + 1, fragment)
+    }
+
+    /// Applies the modifications, returning the new file and the text edits,
+    /// which transform the text of the old file into the text of the new one.
+    ///
+    /// Edits are sorted, do not overlap and refer to the offsets in the old text.
+    /// The new tree is not reparsed, it is exactly the old one with the fragments spliced in.
+    pub fn finish(self) -> (File, Vec<Edit>) {
+        for node in self.replacements.keys().chain(self.insertions.keys()) {
+            if let Some(ancestor) = ancestors(*node).skip(1).find(|a| self.replacements.contains_key(a)) {
+                panic!("conflicting rewrites: {:?} is inside replaced {:?}", node, ancestor)
+            }
+        }
+
+        let mut dirty = HashSet::new();
+        for node in self.replacements.keys().chain(self.insertions.keys()) {
+            dirty.extend(ancestors(*node));
+        }
+
+        let mut edits = Vec::new();
+        let root = self.rebuild(self.file.root(), &self.file.inode(), &dirty, &mut edits)
+            .expect("can't delete the root node");
+        let edits = merge(minimize(&self.file.text().to_cow(), edits));
+        let text = apply(&self.file.text().to_cow(), &edits);
+        let mut embedded = self.file.languages()[1..].to_vec();
+        for fragment in self.replacements.values().flatten().chain(self.insertions.values().flatten().map(|(_, f)| f)) {
+            embedded.extend(fragment.languages.iter().cloned());
+        }
+        let file = File::new_mixed(self.file.language().clone(), embedded, text, FileStats::new(), root);
+        (file, edits)
+    }
+
+    fn set_replacement(&mut self, node: Node<'f>, replacement: Option<Fragment>) {
+        assert!(!self.replacements.contains_key(&node), "node is already rewritten: {:?}", node);
+        self.replacements.insert(node, replacement);
+    }
+
+    fn rebuild(
+        &self,
+        node: Node<'f>,
+        inode: &INode,
+        dirty: &HashSet<Node<'f>>,
+        edits: &mut Vec<Edit>,
+    ) -> Option<INode> {
+        if let Some(replacement) = self.replacements.get(&node) {
+            edits.push(Edit {
+                delete: node.range(),
+                insert: replacement.as_ref().map(|f| f.text.clone()).unwrap_or_default(),
+            });
+            return replacement.as_ref().map(|f| f.inode.clone());
+        }
+        if !dirty.contains(&node) {
+            return Some(inode.clone());
+        }
+
+        let mut insertions: Vec<&(usize, Fragment)> = self.insertions.get(&node)
+            .map(|ins| ins.iter().collect())
+            .unwrap_or_default();
+        insertions.sort_by_key(|&&(idx, _)| idx);
+        let mut insertions = insertions.into_iter().peekable();
+
+        let mut result = INode::new(node.ty());
+        let children: Vec<Node> = node.children().collect();
+        for idx in 0..children.len() + 1 {
+            let offset = children.get(idx).map(|c| c.range().start()).unwrap_or(node.range().end());
+            while let Some((_, fragment)) = insertions.next_if(|&&(i, _)| i == idx) {
+                edits.push(Edit { delete: TextRange::from_len(offset, TextUnit::zero()), insert: fragment.text.clone() });
+                result.push_child(fragment.inode.clone());
+            }
+            if let Some(&child) = children.get(idx) {
+                if let Some(child) = self.rebuild(child, &inode.children()[idx], dirty, edits) {
+                    result.push_child(child);
+                }
+            }
+        }
+        Some(result)
+    }
+}
+
+fn position(node: Node) -> (Node, usize) {
+    let parent = node.parent().expect("root node has no siblings");
+    (parent, node.index_in_parent())
+}
+
+/// Glues together edits, which touch each other.
+fn merge(edits: Vec<Edit>) -> Vec<Edit> {
+    let mut result: Vec<Edit> = Vec::new();
+    for edit in edits {
+        if let Some(last) = result.last_mut() {
+            if last.delete.end() == edit.delete.start() {
+                last.delete = last.delete.glue(edit.delete);
+                last.insert.push_str(&edit.insert);
+                continue;
+            }
+        }
+        result.push(edit);
+    }
+    result
+}
+
+/// Shrinks edits by excluding the common prefix and suffix of the deleted and inserted text.
+fn minimize(text: &str, edits: Vec<Edit>) -> Vec<Edit> {
+    edits.into_iter()
+        .filter_map(|edit| {
+            let deleted = &text[edit.delete];
+            let prefix = common_len(deleted.chars(), edit.insert.chars());
+            let suffix = common_len(deleted[prefix..].chars().rev(), edit.insert[prefix..].chars().rev());
+            if prefix + suffix == deleted.len() && prefix + suffix == edit.insert.len() {
+                return None;
+            }
+            let start = edit.delete.start() + TextUnit::from_usize(prefix);
+            let end = edit.delete.end() - TextUnit::from_usize(suffix);
+            Some(Edit {
+                delete: TextRange::from_to(start, end),
+                insert: edit.insert[prefix..edit.insert.len() - suffix].to_owned(),
+            })
+        })
+        .collect()
+}
+
+fn common_len<A, B>(a: A, b: B) -> usize
+    where A: Iterator<Item=char>, B: Iterator<Item=char>
+{
+    a.zip(b).take_while(|&(x, y)| x == y).map(|(x, _)| x.len_utf8()).sum()
+}
+
+/// Applies sorted non-overlapping `edits` to the `text`.
+pub fn apply(text: &str, edits: &[Edit]) -> String {
+    let mut result = String::new();
+    let mut last = 0;
+    for edit in edits {
+        let start = edit.delete.start().as_u32() as usize;
+        result.push_str(&text[last..start]);
+        result.push_str(&edit.insert);
+        last = edit.delete.end().as_u32() as usize;
+    }
+    result.push_str(&text[last..]);
+    result
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+
+use serde_json::{self, Value};
+
+use {File, Node, INode, Language, FileStats, NodeType, TextUnit, ERROR};
+use registry;
+
+/// Bumped whenever the binary format changes.
+pub const FORMAT_VERSION: u32 = 4;
+
+const MAGIC: &[u8] = b"FALL";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    Corrupted,
+    StaleFormat { version: u32 },
+    WrongLanguage { name: String },
+    StaleLanguage { version: u64 },
+    UnknownLanguage { name: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Corrupted => write!(f, "corrupted data"),
+            Error::StaleFormat { version } =>
+                write!(f, "format version {}, expected {}", version, FORMAT_VERSION),
+            Error::WrongLanguage { ref name } => write!(f, "tree of a different language: {}", name),
+            Error::StaleLanguage { version } => write!(f, "tree of a different language version: {:x}", version),
+            Error::UnknownLanguage { ref name } => write!(f, "embedded language is not registered: {}", name),
+        }
+    }
+}
+
+impl ::std::error::Error for Error {}
+
+/// Encodes the text and the tree of the `file`, together with the names and the versions of its languages.
+pub fn to_bytes(file: &File) -> Vec<u8> {
+    let mut buf = MAGIC.to_vec();
+    write_u64(&mut buf, FORMAT_VERSION as u64);
+    write_u64(&mut buf, file.languages().len() as u64);
+    for lang in file.languages() {
+        write_str(&mut buf, lang.name());
+        write_u64(&mut buf, lang.version());
+    }
+    write_str(&mut buf, &file.text().to_cow());
+    write_inode(&mut buf, &file.inode());
+    buf
+}
+
+/// Decodes a file, previously encoded with `to_bytes`, without reparsing.
+///
+/// Embedded languages of mixed trees are looked up by name in the global registry.
+///
+/// Fails if the data were produced by a different version of the format or of the languages.
+pub fn from_bytes(lang: &Language, bytes: &[u8]) -> Result<File, Error> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(Error::Corrupted);
+    }
+    let mut reader = Reader { bytes: &bytes[MAGIC.len()..] };
+    let format_version = reader.u64()?;
+    if format_version != FORMAT_VERSION as u64 {
+        return Err(Error::StaleFormat { version: format_version as u32 });
+    }
+    let n_languages = reader.u64()?;
+    if n_languages == 0 {
+        return Err(Error::Corrupted);
+    }
+    let name = reader.str()?;
+    if name != lang.name() {
+        return Err(Error::WrongLanguage { name: name.to_owned() });
+    }
+    let version = reader.u64()?;
+    if version != lang.version() {
+        return Err(Error::StaleLanguage { version });
+    }
+    let mut embedded = Vec::new();
+    for _ in 1..n_languages {
+        let name = reader.str()?;
+        let version = reader.u64()?;
+        let lang = registry::global().by_name(name).cloned()
+            .ok_or_else(|| Error::UnknownLanguage { name: name.to_owned() })?;
+        if version != lang.version() {
+            return Err(Error::StaleLanguage { version });
+        }
+        embedded.push(lang);
+    }
+    let text = reader.str()?.to_owned();
+    let inode = reader.inode()?;
+    if !reader.bytes.is_empty() || inode.len() != TextUnit::measure(&text) {
+        return Err(Error::Corrupted);
+    }
+    Ok(File::new_mixed(lang.clone(), embedded, text, FileStats::new(), inode))
+}
+
+/// Exports the tree with node type names, ranges and texts of the tokens, for the consumption by external tools.
+pub fn to_json(file: &File) -> String {
+    let lang = file.language();
+    let json = json!({
+        "language": lang.name(),
+        "version": format!("{:x}", lang.version()),
+        "root": node_to_json(file.root()),
+    });
+    return serde_json::to_string(&json).unwrap();
+
+    fn node_to_json(node: Node) -> Value {
+        let range = node.range();
+        let mut result = json!({
+            "type": node.language().node_type_info(node.ty()).name,
+            "range": [range.start().as_u32(), range.end().as_u32()],
+        });
+        if node.children().next().is_none() {
+            result["text"] = Value::String(node.text().to_string());
+        } else {
+            let children = node.children().map(node_to_json).collect();
+            result["children"] = Value::Array(children);
+        }
+        result
+    }
+}
+
+fn write_u64(buf: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    write_u64(buf, s.len() as u64);
+    buf.extend_from_slice(s.as_bytes());
+}
+
+/// Nodes are written in preorder, as the type and the number of children. Leaves also record their length,
+/// and error nodes record the expected types.
+fn write_inode(buf: &mut Vec<u8>, root: &INode) {
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        write_u64(buf, node.ty().0 as u64);
+        write_u64(buf, node.children().len() as u64);
+        if node.children().is_empty() {
+            write_u64(buf, node.len().as_u32() as u64);
+        }
+        if node.ty() == ERROR {
+            write_u64(buf, node.expected().len() as u64);
+            for ty in node.expected() {
+                write_u64(buf, ty.0 as u64);
+            }
+        }
+        stack.extend(node.children().iter().rev());
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn u64(&mut self) -> Result<u64, Error> {
+        let mut result = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self.bytes.split_first().ok_or(Error::Corrupted)?;
+            self.bytes = rest;
+            result |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(Error::Corrupted)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let value = self.u64()?;
+        if value > u32::MAX as u64 {
+            return Err(Error::Corrupted);
+        }
+        Ok(value as u32)
+    }
+
+    fn str(&mut self) -> Result<&'a str, Error> {
+        let len = self.u64()? as usize;
+        if len > self.bytes.len() {
+            return Err(Error::Corrupted);
+        }
+        let (s, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        ::std::str::from_utf8(s).map_err(|_| Error::Corrupted)
+    }
+
+    fn inode(&mut self) -> Result<INode, Error> {
+        // Nodes under construction, together with the number of children yet to be read.
+        let mut stack: Vec<(INode, u32)> = Vec::new();
+        loop {
+            let ty = NodeType(self.u32()?);
+            let n_children = self.u32()?;
+            let len = if n_children == 0 { self.u32()? } else { 0 };
+            let expected = if ty == ERROR {
+                let n_expected = self.u32()?;
+                Some((0..n_expected).map(|_| self.u32().map(NodeType)).collect::<Result<Vec<_>, _>>()?)
+            } else {
+                None
+            };
+            let mut node = match expected {
+                // Error nodes without children are empty.
+                Some(expected) if n_children == 0 && len == 0 => INode::new_error(expected),
+                _ if n_children == 0 => INode::new_leaf(ty, TextUnit::from_usize(len as usize)),
+                expected => {
+                    let node = expected.map(INode::new_error).unwrap_or_else(|| INode::new(ty));
+                    stack.push((node, n_children));
+                    continue;
+                }
+            };
+            loop {
+                match stack.last_mut() {
+                    None => return Ok(node),
+                    Some(&mut (ref mut parent, ref mut remaining)) => {
+                        parent.push_child(node);
+                        *remaining -= 1;
+                        if *remaining != 0 {
+                            break;
+                        }
+                    }
+                }
+                node = stack.pop().unwrap().0;
+            }
+        }
+    }
+}
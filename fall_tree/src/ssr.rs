@@ -0,0 +1,222 @@
+//! Structural search and replace.
+//!
+//! A pattern is a code snippet with placeholders, like `let $p = $e;`. The snippet is split into
+//! tokens, and a node matches the pattern if its significant tokens are the tokens of the pattern,
+//! where each placeholder stands for a whole node of the tree. So, whitespace and comments do not
+//! matter, and a placeholder never matches half of an expression. A placeholder, which occurs
+//! several times, matches the nodes with the same tokens.
+//!
+//! The replacement is a text with the same placeholders. Matched nodes are replaced as a whole,
+//! so only the trivia inside the nodes of the placeholders survive. To not lose comments, a node
+//! with comments outside of the placeholders doesn't match.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use {File, Node, NodeType, Language, Edit, Preorder, WalkEvent, WHITESPACE};
+use search::ancestors;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Error {
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.message.fmt(f)
+    }
+}
+
+impl ::std::error::Error for Error {}
+
+pub struct Ssr {
+    pattern: Vec<Piece>,
+    replacement: Vec<Chunk>,
+}
+
+#[derive(Debug)]
+enum Piece {
+    Token(NodeType, String),
+    Placeholder(String),
+}
+
+#[derive(Debug)]
+enum Chunk {
+    Text(String),
+    Placeholder(String),
+}
+
+#[derive(Debug)]
+pub struct SsrMatch<'f> {
+    pub node: Node<'f>,
+    /// Nodes matched by the placeholders, by the placeholder names without the `$`.
+    pub placeholders: HashMap<String, Node<'f>>,
+}
+
+impl Ssr {
+    /// Creates a rule, which replaces `pattern` with `replacement` in the files of `lang`.
+    /// Placeholders of the `replacement` are substituted with the text of the matched nodes.
+    pub fn new(lang: &Language, pattern: &str, replacement: &str) -> Result<Ssr, Error> {
+        let mut pieces = Vec::new();
+        for chunk in split_placeholders(pattern) {
+            match chunk {
+                Chunk::Placeholder(name) => pieces.push(Piece::Placeholder(name)),
+                Chunk::Text(text) => {
+                    let file = lang.parse(text);
+                    pieces.extend(file.tokens().significant().map(|t| Piece::Token(t.ty(), t.text().to_string())));
+                }
+            }
+        }
+        if pieces.is_empty() {
+            return Err(Error { message: "empty pattern".to_owned() });
+        }
+        let replacement = split_placeholders(replacement);
+        for chunk in replacement.iter() {
+            if let Chunk::Placeholder(ref name) = *chunk {
+                let known = pieces.iter().any(|p| match *p {
+                    Piece::Placeholder(ref n) => n == name,
+                    _ => false,
+                });
+                if !known {
+                    return Err(Error { message: format!("unknown placeholder ${}", name) });
+                }
+            }
+        }
+        Ok(Ssr { pattern: pieces, replacement })
+    }
+
+    /// Non-overlapping matches, in the document order. For nested matches, the outermost one wins.
+    pub fn matches<'f>(&self, file: &'f File) -> Vec<SsrMatch<'f>> {
+        let mut result = Vec::new();
+        let mut preorder = Preorder::new(file.root());
+        while let Some(event) = preorder.next() {
+            if let WalkEvent::Enter(node) = event {
+                if let Some(m) = self.match_node(node) {
+                    result.push(m);
+                    preorder.skip_subtree();
+                }
+            }
+        }
+        result
+    }
+
+    /// Matches the pattern against exactly this `node`.
+    pub fn match_node<'f>(&self, node: Node<'f>) -> Option<SsrMatch<'f>> {
+        let tokens: Vec<Node> = node.tokens().significant().map(|t| t.node()).collect();
+        let mut placeholders = HashMap::new();
+        if self.match_pieces(&self.pattern, node, &tokens, &mut placeholders) && !has_comments(node, &placeholders) {
+            Some(SsrMatch { node, placeholders })
+        } else {
+            None
+        }
+    }
+
+    /// Text edits, which replace all the matches in the `file`.
+    pub fn edits(&self, file: &File) -> Vec<Edit> {
+        self.matches(file).into_iter()
+            .map(|m| {
+                let mut insert = String::new();
+                for chunk in self.replacement.iter() {
+                    match *chunk {
+                        Chunk::Text(ref text) => insert.push_str(text),
+                        Chunk::Placeholder(ref name) => insert.push_str(&m.placeholders[name].text().to_cow()),
+                    }
+                }
+                Edit { delete: m.node.range(), insert }
+            })
+            .collect()
+    }
+
+    fn match_pieces<'f>(
+        &self,
+        pieces: &[Piece],
+        root: Node<'f>,
+        tokens: &[Node<'f>],
+        placeholders: &mut HashMap<String, Node<'f>>,
+    ) -> bool {
+        let (piece, rest) = match pieces.split_first() {
+            Some(it) => it,
+            None => return tokens.is_empty(),
+        };
+        let token = match tokens.first() {
+            Some(&token) => token,
+            None => return false,
+        };
+        match *piece {
+            Piece::Token(ty, ref text) =>
+                token.ty() == ty && token.text() == text.as_str() && self.match_pieces(rest, root, &tokens[1..], placeholders),
+            Piece::Placeholder(ref name) => {
+                // Candidates are the nodes, which start with the current token, the largest ones first.
+                let candidates: Vec<Node> = ancestors(token)
+                    .take_while(|&node| node != root && node.tokens().significant().next().map(|t| t.node()) == Some(token))
+                    .collect();
+                for &candidate in candidates.iter().rev() {
+                    let n_tokens = candidate.tokens().significant().count();
+                    if n_tokens > tokens.len() {
+                        continue;
+                    }
+                    if let Some(&previous) = placeholders.get(name) {
+                        if !same_tokens(previous, candidate) {
+                            continue;
+                        }
+                        if self.match_pieces(rest, root, &tokens[n_tokens..], placeholders) {
+                            return true;
+                        }
+                        continue;
+                    }
+                    placeholders.insert(name.clone(), candidate);
+                    if self.match_pieces(rest, root, &tokens[n_tokens..], placeholders) {
+                        return true;
+                    }
+                    placeholders.remove(name);
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Whether the `node` has comments, which are not inside any of the `placeholders`.
+fn has_comments(node: Node, placeholders: &HashMap<String, Node>) -> bool {
+    node.tokens()
+        .filter(|t| t.is_trivia() && t.ty() != WHITESPACE)
+        .any(|t| !placeholders.values().any(|p| t.range().is_subrange_of(p.range())))
+}
+
+fn same_tokens(a: Node, b: Node) -> bool {
+    let a = a.tokens().significant().map(|t| (t.ty(), t.text()));
+    let b = b.tokens().significant().map(|t| (t.ty(), t.text()));
+    a.eq(b)
+}
+
+/// Splits the template into text and `$name` placeholders.
+fn split_placeholders(template: &str) -> Vec<Chunk> {
+    let mut result = Vec::new();
+    let mut rest = template;
+    while let Some(idx) = rest.find('$') {
+        let name_len = rest[idx + 1..].find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len() - idx - 1);
+        if name_len == 0 {
+            let (text, tail) = rest.split_at(idx + 1);
+            push_text(&mut result, text);
+            rest = tail;
+            continue;
+        }
+        push_text(&mut result, &rest[..idx]);
+        result.push(Chunk::Placeholder(rest[idx + 1..idx + 1 + name_len].to_owned()));
+        rest = &rest[idx + 1 + name_len..];
+    }
+    push_text(&mut result, rest);
+    return result;
+
+    fn push_text(chunks: &mut Vec<Chunk>, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(&mut Chunk::Text(ref mut last)) = chunks.last_mut() {
+            last.push_str(text);
+            return;
+        }
+        chunks.push(Chunk::Text(text.to_owned()));
+    }
+}
@@ -1,4 +1,5 @@
-use ::{Language, dump_file, dump_file_ws, TextRange, TextUnit, Edit};
+use ::{Language, Node, dump_file, dump_file_ws, TextRange, TextUnit, Edit};
+use diff::{diff, Change};
 use difference::Changeset;
 
 pub fn check_syntax(lang: &Language, input: &str, expected_tree: &str) {
@@ -25,6 +26,19 @@ pub fn check_reparse(
     report_diff(reparsed, &actual_reparsed);
 }
 
+pub fn check_diff(lang: &Language, before: &str, after: &str, expected: &str) {
+    let before_file = lang.parse(before.to_owned());
+    let after_file = lang.parse(after.to_owned());
+    let diff = diff(&before_file, &after_file);
+    let show = |node: Node| format!("{} {:?}", node.language().node_type_info(node.ty()).name, node.text());
+    let actual = diff.changes().iter().map(|&change| match change {
+        Change::Insert(new) => format!("INSERT {}", show(new)),
+        Change::Delete(old) => format!("DELETE {}", show(old)),
+        Change::Replace(old, new) => format!("REPLACE {} -> {}", show(old), show(new)),
+    }).collect::<Vec<_>>().join("\n");
+    report_diff(expected, &actual);
+}
+
 fn make_edit(before: &str, after: &str) -> Edit {
     let prefix = {
         before.as_bytes().iter()
@@ -33,10 +47,12 @@ fn make_edit(before: &str, after: &str) -> Edit {
             .unwrap()
     };
     let suffix = {
+        let max_suffix = ::std::cmp::min(before.len(), after.len()) - prefix;
         before.as_bytes().iter().rev()
             .zip(after.as_bytes().iter().rev())
+            .take(max_suffix)
             .position(|(a, b)| a != b)
-            .unwrap()
+            .unwrap_or(max_suffix)
     };
     let delete = TextRange::from_to(
         TextUnit::from_usize(prefix),
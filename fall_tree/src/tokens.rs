@@ -0,0 +1,140 @@
+use {Node, NodeType, Text, TextRange, Preorder, WalkEvent};
+use search::is_leaf;
+
+/// A non-empty leaf of the tree.
+#[derive(Clone, Copy)]
+pub struct Token<'f> {
+    node: Node<'f>,
+}
+
+impl<'f> ::std::fmt::Debug for Token<'f> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "Token({} {:?})", self.node.language().node_type_info(self.ty()).name, self.text().to_cow())
+    }
+}
+
+impl<'f> PartialEq for Token<'f> {
+    fn eq(&self, other: &Token<'f>) -> bool {
+        self.node == other.node
+    }
+}
+
+impl<'f> Eq for Token<'f> {}
+
+impl<'f> Token<'f> {
+    pub fn node(&self) -> Node<'f> {
+        self.node
+    }
+
+    pub fn ty(&self) -> NodeType {
+        self.node.ty()
+    }
+
+    pub fn range(&self) -> TextRange {
+        self.node.range()
+    }
+
+    pub fn text(&self) -> Text<'f> {
+        self.node.text()
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        self.node.language().is_trivia(self.ty())
+    }
+
+    /// Trivia which belong to this token and precede it.
+    ///
+    /// Trivia between two significant tokens are split at the first trivia token with a line break:
+    /// the ones before it are trailing trivia of the left token, the rest are leading trivia of
+    /// the right token. All trivia at the start of the file are leading, all trivia at the end are trailing.
+    pub fn leading_trivia(&self) -> Vec<Token<'f>> {
+        let mut run = self.trivia_run(|node| node.prev_leaf());
+        run.reverse();
+        if self.neighbour(|node| node.prev_leaf()).is_none() {
+            return run;
+        }
+        match run.iter().position(has_line_break) {
+            Some(idx) => run.split_off(idx),
+            None => Vec::new(),
+        }
+    }
+
+    /// Trivia which belong to this token and follow it. See `leading_trivia` for the attachment policy.
+    pub fn trailing_trivia(&self) -> Vec<Token<'f>> {
+        let mut run = self.trivia_run(|node| node.next_leaf());
+        if self.neighbour(|node| node.next_leaf()).is_none() {
+            return run;
+        }
+        if let Some(idx) = run.iter().position(has_line_break) {
+            run.truncate(idx);
+        }
+        run
+    }
+
+    fn trivia_run<F: Fn(Node<'f>) -> Option<Node<'f>>>(&self, step: F) -> Vec<Token<'f>> {
+        let mut result = Vec::new();
+        let mut node = self.node;
+        while let Some(next) = step(node) {
+            node = next;
+            if !is_leaf(node) {
+                continue;
+            }
+            let token = Token { node };
+            if !token.is_trivia() {
+                break;
+            }
+            result.push(token);
+        }
+        result
+    }
+
+    /// The closest significant token in the direction of the `step`.
+    fn neighbour<F: Fn(Node<'f>) -> Option<Node<'f>>>(&self, step: F) -> Option<Token<'f>> {
+        let mut node = self.node;
+        while let Some(next) = step(node) {
+            node = next;
+            if is_leaf(node) && !node.language().is_trivia(node.ty()) {
+                return Some(Token { node });
+            }
+        }
+        None
+    }
+}
+
+fn has_line_break(token: &Token) -> bool {
+    token.text().find("\n").is_some()
+}
+
+/// Iterator over tokens of a subtree, in the document order.
+pub struct Tokens<'f> {
+    preorder: Preorder<'f>,
+}
+
+impl<'f> Tokens<'f> {
+    pub fn new(root: Node<'f>) -> Tokens<'f> {
+        Tokens { preorder: Preorder::new(root) }
+    }
+
+    /// Skips trivia tokens.
+    pub fn significant(self) -> ::std::iter::Filter<Tokens<'f>, fn(&Token<'f>) -> bool> {
+        fn is_significant(token: &Token) -> bool {
+            !token.is_trivia()
+        }
+        self.filter(is_significant)
+    }
+}
+
+impl<'f> Iterator for Tokens<'f> {
+    type Item = Token<'f>;
+
+    fn next(&mut self) -> Option<Token<'f>> {
+        for event in &mut self.preorder {
+            if let WalkEvent::Enter(node) = event {
+                if is_leaf(node) {
+                    return Some(Token { node });
+                }
+            }
+        }
+        None
+    }
+}
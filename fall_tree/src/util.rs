@@ -1,32 +1,77 @@
-use {File, Node, WHITESPACE, Language};
+use {File, Node, WHITESPACE};
 use std::fmt::Write;
 
 pub fn dump_file(f: &File) -> String {
-    dump(f.language(), f.root(), &f.text().to_cow(), false)
+    dump(f.root(), &f.text().to_cow(), false)
 }
 
 pub fn dump_file_ws(f: &File) -> String {
-    dump(f.language(), f.root(), &f.text().to_cow(), true)
+    dump(f.root(), &f.text().to_cow(), true)
 }
 
 pub fn walk_tree<F: FnMut(Node)>(node: Node, mut f: F) {
-    go(node, &mut f);
+    for event in Preorder::new(node) {
+        if let WalkEvent::Enter(node) = event {
+            f(node)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WalkEvent<'f> {
+    Enter(Node<'f>),
+    Leave(Node<'f>),
+}
+
+/// Non-recursive traversal of a subtree, which emits `Enter` event when the node
+/// is visited for the first time and `Leave` event after all its children are visited.
+pub struct Preorder<'f> {
+    root: Node<'f>,
+    last: Option<WalkEvent<'f>>,
+    next: Option<WalkEvent<'f>>,
+}
 
-    fn go<F: FnMut(Node)>(node: Node, f: &mut F) {
-        f(node);
-        for child in node.children() {
-            go(child, f)
+impl<'f> Preorder<'f> {
+    pub fn new(root: Node<'f>) -> Preorder<'f> {
+        Preorder { root, last: None, next: Some(WalkEvent::Enter(root)) }
+    }
+
+    /// Skips the children of the node which was just entered. The next event is leaving the node.
+    pub fn skip_subtree(&mut self) {
+        if let Some(WalkEvent::Enter(node)) = self.last {
+            self.next = Some(WalkEvent::Leave(node))
         }
     }
 }
 
+impl<'f> Iterator for Preorder<'f> {
+    type Item = WalkEvent<'f>;
+
+    fn next(&mut self) -> Option<WalkEvent<'f>> {
+        let event = self.next?;
+        self.next = match event {
+            WalkEvent::Enter(node) => Some(match node.first_child() {
+                Some(child) => WalkEvent::Enter(child),
+                None => WalkEvent::Leave(node),
+            }),
+            WalkEvent::Leave(node) if node == self.root => None,
+            WalkEvent::Leave(node) => Some(match node.next_sibling() {
+                Some(sibling) => WalkEvent::Enter(sibling),
+                None => WalkEvent::Leave(node.parent().unwrap()),
+            }),
+        };
+        self.last = Some(event);
+        Some(event)
+    }
+}
+
 
-fn dump(lang: &Language, root: Node, text: &str, include_whitespace: bool) -> String {
+fn dump(root: Node, text: &str, include_whitespace: bool) -> String {
     let mut buf = String::new();
-    go(lang, 0, root, text, &mut buf, include_whitespace);
+    go(0, root, text, &mut buf, include_whitespace);
     return buf;
 
-    fn go(lang: &Language, level: usize, n: Node, text: &str, buf: &mut String, include_whitespace: bool) {
+    fn go(level: usize, n: Node, text: &str, buf: &mut String, include_whitespace: bool) {
         if n.ty() == WHITESPACE && !include_whitespace {
             return
         }
@@ -35,7 +80,7 @@ fn dump(lang: &Language, root: Node, text: &str, include_whitespace: bool) -> St
             buf.push_str("  ")
         }
 
-        let ty_name = lang.node_type_info(n.ty()).name;
+        let ty_name = n.language().node_type_info(n.ty()).name;
         if n.children().next().is_none() {
             write!(buf, "{} {:?}\n", ty_name, &text[n.range()])
                 .unwrap();
@@ -43,7 +88,7 @@ fn dump(lang: &Language, root: Node, text: &str, include_whitespace: bool) -> St
             write!(buf, "{}\n", ty_name)
                 .unwrap();
             for child in n.children() {
-                go(lang, level + 1, child, text, buf, include_whitespace);
+                go(level + 1, child, text, buf, include_whitespace);
             }
         }
     }
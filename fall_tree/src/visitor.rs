@@ -1,5 +1,5 @@
 use std::marker::PhantomData;
-use {Node, AstNode, NodeType};
+use {Node, AstNode, NodeType, Preorder, WalkEvent};
 
 pub trait NodeVisitor<'f, C> {
     fn context(&mut self) -> &mut C;
@@ -16,10 +16,11 @@ pub trait NodeVisitor<'f, C> {
     }
 
     fn walk_recursively_children_first(&mut self, node: Node<'f>) {
-        for child in node.children() {
-            self.walk_recursively_children_first(child);
+        for event in Preorder::new(node) {
+            if let WalkEvent::Leave(node) = event {
+                self.do_visit(node)
+            }
         }
-        self.do_visit(node)
     }
 }
 
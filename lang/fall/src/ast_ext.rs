@@ -1,9 +1,9 @@
 use fall_tree::{Text, TextRange, AstNode, Node, NodeType};
-use fall_tree::search::{children_of_type, child_of_type_exn, child_of_type, ast_parent_exn};
+use fall_tree::search::{children_of_type, child_of_type_exn, child_of_type, ast_parent, ast_parent_exn};
 
 use ::{STRING, IDENT, SIMPLE_STRING, HASH_STRING, AST_SELECTOR, QUESTION, DOT, STAR, KW_PUB,
-       LexRule, SynRule, FallFile, VerbatimDef, MethodDef,
-       RefExpr, AstClassDef, AstDef, Expr, Attributes};
+       LanguageDef, LexRule, SynRule, FallFile, VerbatimDef, MethodDef,
+       RefExpr, CallExpr, AstClassDef, AstDef, Expr, Attributes};
 
 impl<'f> FallFile<'f> {
     pub fn resolve_rule(&self, name: Text<'f>) -> Option<SynRule<'f>> {
@@ -34,6 +34,12 @@ impl<'f> FallFile<'f> {
     }
 }
 
+impl<'f> LanguageDef<'f> {
+    pub fn extensions(&self) -> Vec<Text<'f>> {
+        children_of_type(self.node(), STRING).map(|n| lit_body(n.text())).collect()
+    }
+}
+
 impl<'f> LexRule<'f> {
     pub fn token_re(&self) -> Option<String> {
         let raw = match self.raw_re() {
@@ -54,6 +60,25 @@ impl<'f> LexRule<'f> {
         })
     }
 
+    pub fn is_trivia(&self) -> bool {
+        self.attributes().is_some_and(|attrs| attrs.is_trivia())
+    }
+
+    pub fn is_literal(&self) -> bool {
+        self.attributes().is_some_and(|attrs| attrs.is_literal())
+    }
+
+    /// Whether the token is a keyword only in some contexts. Such tokens are not produced by the lexer,
+    /// the parser retags other tokens with the same text, like identifiers.
+    pub fn is_contextual(&self) -> bool {
+        self.attributes().is_some_and(|attrs| attrs.is_contextual())
+    }
+
+    /// Text of the token, if it is matched by a fixed string rather than by a regex.
+    pub fn fixed_text(&self) -> Option<Text<'f>> {
+        self.raw_re().filter(|r| r.starts_with("'")).map(lit_body)
+    }
+
     pub fn token_name(&self) -> Text<'f> {
         if let Some(r) = self.raw_re() {
             if r.starts_with("'") {
@@ -143,8 +168,10 @@ pub enum SelectorKind<'f> {
 }
 
 pub enum RefKind<'f> {
-    Token(usize),
+    Token(usize, LexRule<'f>),
     RuleReference(SynRule<'f>),
+    /// A parameter of the enclosing rule, by its position.
+    Param(usize),
 }
 
 impl<'f> RefExpr<'f> {
@@ -152,6 +179,11 @@ impl<'f> RefExpr<'f> {
         let file = ast_parent_exn::<FallFile>(self.node());
 
         if let Some(ident) = child_of_type(self.node(), IDENT) {
+            let param = ast_parent::<SynRule>(self.node())
+                .and_then(|rule| rule.parameters().position(|p| p.name() == ident.text()));
+            if let Some(idx) = param {
+                return Some(RefKind::Param(idx))
+            }
             if let Some(rule) = file.resolve_rule(ident.text()) {
                 return Some(RefKind::RuleReference(rule))
             }
@@ -161,20 +193,26 @@ impl<'f> RefExpr<'f> {
             .text();
 
         match file.tokenizer_def().and_then(|td| td.lex_rules().find(|r| r.token_name() == token_name)) {
-            Some(rule) => {
-                let ty_name = rule.node_type();
-                file.resolve_ty(ty_name).map(RefKind::Token)
-            }
+            Some(rule) => file.resolve_ty(rule.node_type()).map(|idx| RefKind::Token(idx, rule)),
             None => None,
         }
     }
 }
 
+impl<'f> CallExpr<'f> {
+    /// The parameterized rule, which is called with the arguments, like `<balanced_body '[' ']'>`.
+    pub fn resolve_rule(&self) -> Option<SynRule<'f>> {
+        let file = ast_parent_exn::<FallFile>(self.node());
+        file.resolve_rule(self.fn_name())
+            .filter(|rule| rule.parameters().next().is_some())
+    }
+}
+
 impl<'f> Expr<'f> {
     pub fn token_set(&self) -> Option<Vec<usize>> {
         match *self {
             Expr::RefExpr(ref_) => {
-                if let Some(RefKind::Token(idx)) = ref_.resolve() {
+                if let Some(RefKind::Token(idx, _)) = ref_.resolve() {
                     Some(vec![idx])
                 } else {
                     None
@@ -213,9 +251,41 @@ impl<'f> Attributes<'f> {
         self.attributes().any(|attr| attr.name() == "pratt")
     }
 
+    pub fn is_trivia(&self) -> bool {
+        self.attributes().any(|attr| attr.name() == "trivia")
+    }
+
+    pub fn is_literal(&self) -> bool {
+        self.attributes().any(|attr| attr.name() == "literal")
+    }
+
+    pub fn is_memo(&self) -> bool {
+        self.attributes().any(|attr| attr.name() == "memo")
+    }
+
+    pub fn is_contextual(&self) -> bool {
+        self.attributes().any(|attr| attr.name() == "contextual")
+    }
+
     pub fn bin_priority(&self) -> Option<u32> {
+        self.priority("bin")
+    }
+
+    pub fn prefix_priority(&self) -> Option<u32> {
+        self.priority("prefix")
+    }
+
+    pub fn postfix_priority(&self) -> Option<u32> {
+        self.priority("postfix")
+    }
+
+    pub fn is_right(&self) -> bool {
+        self.attributes().any(|attr| attr.name() == "right")
+    }
+
+    fn priority(&self, name: &str) -> Option<u32> {
         self.attributes()
-            .find(|attr| attr.name() == "bin")
+            .find(|attr| attr.name() == name)
             .map(|attr| attr.value().unwrap().to_cow().parse().unwrap())
     }
 
@@ -0,0 +1,64 @@
+use fall_tree::{File, TextRange, Edit};
+use fall_tree::format::{Formatter, Rule};
+use fall_tree::indent::Indenter;
+use ::*;
+
+lazy_static! {
+    static ref INDENTER: Indenter = Indenter::new()
+        .unit("  ")
+        .indent(TOKENIZER_DEF)
+        .indent(AST_DEF)
+        .indent(AST_NODE_DEF)
+        .indent(AST_CLASS_DEF)
+        .indent(BLOCK_EXPR);
+
+    static ref FORMATTER: Formatter = Formatter::new()
+        .indenter(INDENTER.clone())
+        .rule(Rule::inside(FALL_FILE).newline())
+        .rule(Rule::inside(LANGUAGE_DEF).space())
+
+        .rule(Rule::inside(TOKENIZER_DEF).after(KW_TOKENIZER).space())
+        .rule(Rule::inside(TOKENIZER_DEF).newline())
+
+        .rule(Rule::inside(ATTRIBUTES).after(COMMA).space())
+        .rule(Rule::inside(ATTRIBUTES).empty())
+        .rule(Rule::inside(ATTRIBUTE).empty())
+
+        .rule(Rule::inside(SYN_RULE).after(ATTRIBUTES).before(KW_PUB).newline())
+        .rule(Rule::inside(SYN_RULE).after(ATTRIBUTES).before(KW_RULE).newline())
+        .rule(Rule::inside(SYN_RULE).before(LPAREN).empty())
+        .rule(Rule::inside(SYN_RULE).after(LPAREN).empty())
+        .rule(Rule::inside(SYN_RULE).before(RPAREN).empty())
+        .rule(Rule::inside(SYN_RULE).before(COMMA).empty())
+        .rule(Rule::inside(SYN_RULE).space().inline())
+        .rule(Rule::inside(CALL_EXPR).after(LANGLE).empty())
+        .rule(Rule::inside(CALL_EXPR).before(RANGLE).empty())
+        .rule(Rule::inside(CALL_EXPR).space().inline())
+        .rule(Rule::inside(SEQ_EXPR).space().inline())
+
+        .rule(Rule::inside(AST_DEF).after(KW_AST).space())
+        .rule(Rule::inside(AST_DEF).newline())
+        .rule(Rule::inside(AST_NODE_DEF).after(LBRACE).before(RBRACE).empty())
+        .rule(Rule::inside(AST_NODE_DEF).after(LBRACE).newline())
+        .rule(Rule::inside(AST_NODE_DEF).before(RBRACE).newline())
+        .rule(Rule::inside(AST_NODE_DEF).after(METHOD_DEF).newline())
+        .rule(Rule::inside(AST_NODE_DEF).space())
+        .rule(Rule::inside(AST_CLASS_DEF).after(LBRACE).newline())
+        .rule(Rule::inside(AST_CLASS_DEF).before(RBRACE).newline())
+        .rule(Rule::inside(AST_CLASS_DEF).after(KW_CLASS).space())
+        .rule(Rule::inside(AST_CLASS_DEF).before(LBRACE).space())
+        .rule(Rule::inside(METHOD_DEF).space())
+        .rule(Rule::inside(AST_SELECTOR).empty());
+}
+
+/// Whitespace edits, which format the part of the `file` within the `range`.
+///
+/// Token definitions and rule bodies keep their layout, because they are often aligned by hand.
+pub fn format(file: &File, range: TextRange) -> Vec<Edit> {
+    FORMATTER.format(file, range)
+}
+
+/// Indentation of the lines of the `file`.
+pub fn indenter() -> &'static Indenter {
+    &INDENTER
+}
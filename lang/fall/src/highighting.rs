@@ -11,24 +11,30 @@ pub fn highlight(file: &File) -> Spans {
     let mut spans = vec![];
     Visitor(&mut spans)
         .visit_nodes(&[HASH_STRING, SIMPLE_STRING], |spans, node| {
-            colorize_node(node, "string", spans)
+            // Strings with injected code are colored by the embedded language, except for the quotes.
+            if node.children().next().is_none() {
+                colorize_node(node, "string", spans)
+            }
         })
-        .visit_nodes(&[KW_RULE, KW_VERBATIM, KW_TOKENIZER, KW_AST, KW_NODE, KW_CLASS, KW_PUB], |spans, node| {
+        .visit_nodes(&[KW_RULE, KW_VERBATIM, KW_TOKENIZER, KW_AST, KW_NODE, KW_CLASS, KW_PUB, KW_LANGUAGE], |spans, node| {
             colorize_node(node, "keyword", spans)
         })
         .visit_nodes(&[ERROR], |spans, node| {
-            spans.push((node.range().start().as_u32(), node.range().end().as_u32() + 1, "error"))
+            if node.language().tag() == LANG_FALL.tag() {
+                spans.push((node.range().start().as_u32(), node.range().end().as_u32() + 1, "error"))
+            }
         })
         .visit::<LexRule, _>(|spans, rule| colorize_child(rule.node(), IDENT, "token", spans))
         .visit::<SynRule, _>(|spans, rule| colorize_child(rule.node(), IDENT, "rule", spans))
         .visit::<AstNodeDef, _>(|spans, rule| colorize_child(rule.node(), IDENT, "rule", spans))
         .visit::<RefExpr, _>(|spans, ref_| match ref_.resolve() {
-            Some(RefKind::Token(_)) => colorize_node(ref_.node(), "token", spans),
+            Some(RefKind::Token(..)) => colorize_node(ref_.node(), "token", spans),
             Some(RefKind::RuleReference { .. }) => colorize_node(ref_.node(), "rule", spans),
-            None => {}
+            Some(RefKind::Param(_)) | None => {}
         })
         .visit::<CallExpr, _>(|spans, call| {
-            colorize_child(call.node(), IDENT, "builtin", spans);
+            let color = if call.resolve_rule().is_some() { "rule" } else { "builtin" };
+            colorize_child(call.node(), IDENT, color, spans);
             colorize_child(call.node(), LANGLE, "builtin", spans);
             colorize_child(call.node(), RANGLE, "builtin", spans);
         })
@@ -36,6 +42,18 @@ pub fn highlight(file: &File) -> Spans {
             colorize_node(attrs.node(), "meta", spans)
         })
         .walk_recursively_children_first(file.node());
+
+    for token in file.node().tokens() {
+        if token.ty().language_tag() == LANG_FALL.tag() {
+            continue;
+        }
+        let info = token.node().language().node_type_info(token.ty());
+        if info.is_keyword {
+            colorize_node(token.node(), "keyword", &mut spans)
+        } else if info.is_literal {
+            colorize_node(token.node(), "literal", &mut spans)
+        }
+    }
     spans
 }
 
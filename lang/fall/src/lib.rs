@@ -11,11 +11,13 @@ use fall_tree::{AstNode, File};
 mod syntax;
 mod ast_ext;
 mod highighting;
+mod formatting;
 
 pub use self::syntax::*;
 pub use self::ast_ext::{SelectorKind, RefKind};
 pub use self::syntax::LANG as LANG_FALL;
 pub use highighting::highlight;
+pub use formatting::{format, indenter};
 
 pub fn ast(file: &File) -> FallFile {
     FallFile::new(file.root())
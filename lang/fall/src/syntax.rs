@@ -1,155 +1,539 @@
 use serde_json;
-use fall_tree::{NodeType, NodeTypeInfo, Language, LanguageImpl, FileStats, INode};
+use fall_tree::{NodeType, NodeTypeInfo, Language, LanguageImpl, Injection, FileStats, INode, File, Edit};
+use fall_tree::registry::Registry;
 pub use fall_tree::{ERROR, WHITESPACE};
 
-pub const EQ: NodeType = NodeType(100);
-pub const PIPE: NodeType = NodeType(101);
-pub const STAR: NodeType = NodeType(102);
-pub const QUESTION: NodeType = NodeType(103);
-pub const DOT: NodeType = NodeType(104);
-pub const COMMA: NodeType = NodeType(105);
-pub const HASH: NodeType = NodeType(106);
-pub const LBRACE: NodeType = NodeType(107);
-pub const RBRACE: NodeType = NodeType(108);
-pub const LBRACK: NodeType = NodeType(109);
-pub const RBRACK: NodeType = NodeType(110);
-pub const LANGLE: NodeType = NodeType(111);
-pub const RANGLE: NodeType = NodeType(112);
-pub const LPAREN: NodeType = NodeType(113);
-pub const RPAREN: NodeType = NodeType(114);
-pub const KW_NODE: NodeType = NodeType(115);
-pub const KW_CLASS: NodeType = NodeType(116);
-pub const KW_TOKENIZER: NodeType = NodeType(117);
-pub const KW_RULE: NodeType = NodeType(118);
-pub const KW_VERBATIM: NodeType = NodeType(119);
-pub const KW_AST: NodeType = NodeType(120);
-pub const KW_PUB: NodeType = NodeType(121);
-pub const NUMBER: NodeType = NodeType(122);
-pub const SIMPLE_STRING: NodeType = NodeType(123);
-pub const HASH_STRING: NodeType = NodeType(124);
-pub const IDENT: NodeType = NodeType(125);
-pub const FALL_FILE: NodeType = NodeType(126);
-pub const TOKENIZER_DEF: NodeType = NodeType(127);
-pub const LEX_RULE: NodeType = NodeType(128);
-pub const SYN_RULE: NodeType = NodeType(129);
-pub const ATTRIBUTES: NodeType = NodeType(130);
-pub const ATTRIBUTE: NodeType = NodeType(131);
-pub const STRING: NodeType = NodeType(132);
-pub const VERBATIM_DEF: NodeType = NodeType(133);
-pub const AST_DEF: NodeType = NodeType(134);
-pub const AST_NODE_DEF: NodeType = NodeType(135);
-pub const AST_CLASS_DEF: NodeType = NodeType(136);
-pub const METHOD_DEF: NodeType = NodeType(137);
-pub const AST_SELECTOR: NodeType = NodeType(138);
-pub const REF_EXPR: NodeType = NodeType(139);
-pub const CALL_EXPR: NodeType = NodeType(140);
-pub const SEQ_EXPR: NodeType = NodeType(141);
-pub const BLOCK_EXPR: NodeType = NodeType(142);
+pub const EQ: NodeType = NodeType((63382 << 16) | 100);
+pub const PIPE: NodeType = NodeType((63382 << 16) | 101);
+pub const STAR: NodeType = NodeType((63382 << 16) | 102);
+pub const QUESTION: NodeType = NodeType((63382 << 16) | 103);
+pub const DOT: NodeType = NodeType((63382 << 16) | 104);
+pub const COMMA: NodeType = NodeType((63382 << 16) | 105);
+pub const HASH: NodeType = NodeType((63382 << 16) | 106);
+pub const LBRACE: NodeType = NodeType((63382 << 16) | 107);
+pub const RBRACE: NodeType = NodeType((63382 << 16) | 108);
+pub const LBRACK: NodeType = NodeType((63382 << 16) | 109);
+pub const RBRACK: NodeType = NodeType((63382 << 16) | 110);
+pub const LANGLE: NodeType = NodeType((63382 << 16) | 111);
+pub const RANGLE: NodeType = NodeType((63382 << 16) | 112);
+pub const LPAREN: NodeType = NodeType((63382 << 16) | 113);
+pub const RPAREN: NodeType = NodeType((63382 << 16) | 114);
+pub const KW_NODE: NodeType = NodeType((63382 << 16) | 115);
+pub const KW_CLASS: NodeType = NodeType((63382 << 16) | 116);
+pub const KW_TOKENIZER: NodeType = NodeType((63382 << 16) | 117);
+pub const KW_RULE: NodeType = NodeType((63382 << 16) | 118);
+pub const KW_VERBATIM: NodeType = NodeType((63382 << 16) | 119);
+pub const KW_AST: NodeType = NodeType((63382 << 16) | 120);
+pub const KW_PUB: NodeType = NodeType((63382 << 16) | 121);
+pub const KW_LANGUAGE: NodeType = NodeType((63382 << 16) | 122);
+pub const NUMBER: NodeType = NodeType((63382 << 16) | 123);
+pub const SIMPLE_STRING: NodeType = NodeType((63382 << 16) | 124);
+pub const HASH_STRING: NodeType = NodeType((63382 << 16) | 125);
+pub const IDENT: NodeType = NodeType((63382 << 16) | 126);
+pub const FALL_FILE: NodeType = NodeType((63382 << 16) | 127);
+pub const LANGUAGE_DEF: NodeType = NodeType((63382 << 16) | 128);
+pub const TOKENIZER_DEF: NodeType = NodeType((63382 << 16) | 129);
+pub const LEX_RULE: NodeType = NodeType((63382 << 16) | 130);
+pub const SYN_RULE: NodeType = NodeType((63382 << 16) | 131);
+pub const PARAMETER: NodeType = NodeType((63382 << 16) | 132);
+pub const ATTRIBUTES: NodeType = NodeType((63382 << 16) | 133);
+pub const ATTRIBUTE: NodeType = NodeType((63382 << 16) | 134);
+pub const STRING: NodeType = NodeType((63382 << 16) | 135);
+pub const VERBATIM_DEF: NodeType = NodeType((63382 << 16) | 136);
+pub const AST_DEF: NodeType = NodeType((63382 << 16) | 137);
+pub const AST_NODE_DEF: NodeType = NodeType((63382 << 16) | 138);
+pub const AST_CLASS_DEF: NodeType = NodeType((63382 << 16) | 139);
+pub const METHOD_DEF: NodeType = NodeType((63382 << 16) | 140);
+pub const AST_SELECTOR: NodeType = NodeType((63382 << 16) | 141);
+pub const REF_EXPR: NodeType = NodeType((63382 << 16) | 142);
+pub const CALL_EXPR: NodeType = NodeType((63382 << 16) | 143);
+pub const SEQ_EXPR: NodeType = NodeType((63382 << 16) | 144);
+pub const BLOCK_EXPR: NodeType = NodeType((63382 << 16) | 145);
 
 lazy_static! {
     pub static ref LANG: Language = {
-        use fall_parse::{LexRule, SynRule, Parser};
+        use fall_parse::{LexRule, Trivia, SynRule, Parser};
         const ALL_NODE_TYPES: &[NodeType] = &[
             ERROR, WHITESPACE,
-            EQ, PIPE, STAR, QUESTION, DOT, COMMA, HASH, LBRACE, RBRACE, LBRACK, RBRACK, LANGLE, RANGLE, LPAREN, RPAREN, KW_NODE, KW_CLASS, KW_TOKENIZER, KW_RULE, KW_VERBATIM, KW_AST, KW_PUB, NUMBER, SIMPLE_STRING, HASH_STRING, IDENT, FALL_FILE, TOKENIZER_DEF, LEX_RULE, SYN_RULE, ATTRIBUTES, ATTRIBUTE, STRING, VERBATIM_DEF, AST_DEF, AST_NODE_DEF, AST_CLASS_DEF, METHOD_DEF, AST_SELECTOR, REF_EXPR, CALL_EXPR, SEQ_EXPR, BLOCK_EXPR,
+            EQ, PIPE, STAR, QUESTION, DOT, COMMA, HASH, LBRACE, RBRACE, LBRACK, RBRACK, LANGLE, RANGLE, LPAREN, RPAREN, KW_NODE, KW_CLASS, KW_TOKENIZER, KW_RULE, KW_VERBATIM, KW_AST, KW_PUB, KW_LANGUAGE, NUMBER, SIMPLE_STRING, HASH_STRING, IDENT, FALL_FILE, LANGUAGE_DEF, TOKENIZER_DEF, LEX_RULE, SYN_RULE, PARAMETER, ATTRIBUTES, ATTRIBUTE, STRING, VERBATIM_DEF, AST_DEF, AST_NODE_DEF, AST_CLASS_DEF, METHOD_DEF, AST_SELECTOR, REF_EXPR, CALL_EXPR, SEQ_EXPR, BLOCK_EXPR,
         ];
-        let parser_json = r##"[{"body":{"Pub":[28,{"Or":[{"And":[[{"Rep":{"WithSkip":[{"Rule":2},{"Rule":1}]}}],null]}]}]}},{"body":{"Or":[{"And":[[{"Rule":3}],null]},{"And":[[{"Rule":5}],null]},{"And":[[{"Rule":9}],null]},{"And":[[{"Rule":10}],null]}]}},{"body":{"Or":[{"And":[[{"Token":19}],null]},{"And":[[{"Token":23}],null]},{"And":[[{"Token":20}],null]},{"And":[[{"Token":8}],null]},{"And":[[{"Token":21}],null]},{"And":[[{"Token":22}],null]}]}},{"body":{"Pub":[29,{"Or":[{"And":[[{"Token":19},{"Token":9},{"Rep":{"Rule":4}},{"Token":10}],1]}]}]}},{"body":{"Pub":[30,{"Or":[{"And":[[{"Token":27},{"Rule":8},{"Opt":{"Rule":8}}],1]}]}]}},{"body":{"Pub":[31,{"Or":[{"And":[[{"Opt":{"Rule":6}},{"Opt":{"Token":23}},{"Token":20},{"Token":27},{"Rule":20}],3]}]}]}},{"body":{"Pub":[32,{"Or":[{"And":[[{"Token":8},{"Token":11},{"Rep":{"Or":[{"And":[[{"Rule":7},{"Or":[{"And":[[{"Token":7}],null]},{"And":[[],null]}]}],null]}]}},{"Token":12}],null]}]}]}},{"body":{"Pub":[33,{"Or":[{"And":[[{"Token":27},{"Opt":{"Or":[{"And":[[{"Token":15},{"Token":24},{"Token":16}],null]}]}}],null]}]}]}},{"body":{"Pub":[34,{"Or":[{"And":[[{"Token":25}],null]},{"And":[[{"Token":26}],null]}]}]}},{"body":{"Pub":[35,{"Or":[{"And":[[{"Token":21},{"Token":26}],1]}]}]}},{"body":{"Pub":[36,{"Or":[{"And":[[{"Token":22},{"Token":9},{"Rep":{"WithSkip":[{"Or":[{"And":[[{"Token":17}],null]},{"And":[[{"Token":18}],null]}]},{"Or":[{"And":[[{"Rule":11}],null]},{"And":[[{"Rule":12}],null]}]}]}},{"Token":10}],1]}]}]}},{"body":{"Pub":[37,{"Or":[{"And":[[{"Token":17},{"Token":27},{"Token":9},{"Rep":{"Rule":13}},{"Token":10}],1]}]}]}},{"body":{"Pub":[38,{"Or":[{"And":[[{"Token":18},{"Token":27},{"Token":9},{"Layer":[{"Rule":21},{"Rep":{"Token":27}}]},{"Token":10}],1]}]}]}},{"body":{"Pub":[39,{"Or":[{"And":[[{"Token":27},{"Rule":14}],null]}]}]}},{"body":{"Pub":[40,{"Or":[{"And":[[{"Token":27},{"Opt":{"Rule":15}}],null]}]}]}},{"body":{"Or":[{"And":[[{"Token":5},{"Token":6},{"Token":27}],null]},{"And":[[{"Token":6},{"Token":27}],null]},{"And":[[{"Token":4}],null]},{"And":[[{"Token":5}],null]}]}},{"body":{"Or":[{"And":[[{"Rule":18}],null]},{"And":[[{"Rule":17}],null]},{"And":[[{"Rule":20}],null]}]}},{"body":{"Pub":[41,{"Or":[{"And":[[{"Token":27}],null]},{"And":[[{"Token":25}],null]}]}]}},{"body":{"Pub":[42,{"Or":[{"And":[[{"Token":13},{"Token":27},{"Rep":{"Rule":16}},{"Token":14}],null]}]}]}},{"body":{"Pub":[43,{"Or":[{"And":[[{"Rep":{"Rule":16}}],null]}]}]}},{"body":{"Pub":[44,{"Or":[{"And":[[{"Token":9},{"Layer":[{"Rule":21},{"Or":[{"And":[[{"Opt":{"Rule":19}},{"Rep":{"Or":[{"And":[[{"Token":3},{"Rule":19}],null]}]}}],null]}]}]},{"Token":10}],null]}]}]}},{"body":{"Or":[{"And":[[{"Rep":{"Rule":22}}],null]}]}},{"body":{"Or":[{"And":[[{"Token":9},{"Rule":21},{"Token":10}],1]},{"And":[[{"Not":[10]}],null]}]}}]"##;
+        let parser_json = r##"[{"body":{"Pub":[29,{"Or":[{"And":[[{"Rep":{"WithSkip":[{"Rule":2},{"Rule":1}]}}],null]}]}]}},{"body":{"Or":[{"And":[[{"Rule":3}],null]},{"And":[[{"Rule":4}],null]},{"And":[[{"Rule":6}],null]},{"And":[[{"Rule":11}],null]},{"And":[[{"Rule":12}],null]}]}},{"body":{"Or":[{"And":[[{"Token":24}],null]},{"And":[[{"Token":19}],null]},{"And":[[{"Token":23}],null]},{"And":[[{"Token":20}],null]},{"And":[[{"Token":8}],null]},{"And":[[{"Token":21}],null]},{"And":[[{"Token":22}],null]}]}},{"body":{"Pub":[30,{"Or":[{"And":[[{"Opt":{"Rule":8}},{"Token":24},{"Token":28},{"Rep":{"Rule":10}}],2]}]}]}},{"body":{"Pub":[31,{"Or":[{"And":[[{"Token":19},{"Token":9},{"Rep":{"Rule":5}},{"Token":10}],1]}]}]}},{"body":{"Pub":[32,{"Or":[{"And":[[{"Opt":{"Rule":8}},{"Token":28},{"Rule":10},{"Opt":{"Rule":10}}],2]}]}]}},{"body":{"Pub":[33,{"Or":[{"And":[[{"Opt":{"Rule":8}},{"Opt":{"Token":23}},{"Token":20},{"Token":28},{"Opt":{"Or":[{"And":[[{"Token":15},{"Opt":{"Or":[{"And":[[{"Rule":7},{"Rep":{"Or":[{"And":[[{"Token":7},{"Rule":7}],null]}]}}],null]}]}},{"Token":16}],null]}]}},{"Rule":22}],3]}]}]}},{"body":{"Pub":[34,{"Or":[{"And":[[{"Token":28}],null]}]}]}},{"body":{"Pub":[35,{"Or":[{"And":[[{"Token":8},{"Token":11},{"Rep":{"Or":[{"And":[[{"Rule":9},{"Or":[{"And":[[{"Token":7}],null]},{"And":[[],null]}]}],null]}]}},{"Token":12}],null]}]}]}},{"body":{"Pub":[36,{"Or":[{"And":[[{"Token":28},{"Opt":{"Or":[{"And":[[{"Token":15},{"Token":25},{"Token":16}],null]}]}}],null]}]}]}},{"body":{"Pub":[37,{"Or":[{"And":[[{"Token":26}],null]},{"And":[[{"Token":27}],null]}]}]}},{"body":{"Pub":[38,{"Or":[{"And":[[{"Token":21},{"Token":27}],1]}]}]}},{"body":{"Pub":[39,{"Or":[{"And":[[{"Token":22},{"Token":9},{"Rep":{"WithSkip":[{"Or":[{"And":[[{"Token":17}],null]},{"And":[[{"Token":18}],null]}]},{"Or":[{"And":[[{"Rule":13}],null]},{"And":[[{"Rule":14}],null]}]}]}},{"Token":10}],1]}]}]}},{"body":{"Pub":[40,{"Or":[{"And":[[{"Token":17},{"Token":28},{"Token":9},{"Rep":{"Rule":15}},{"Token":10}],1]}]}]}},{"body":{"Pub":[41,{"Or":[{"And":[[{"Token":18},{"Token":28},{"Token":9},{"Layer":[{"Rule":25},{"Rep":{"Token":28}}]},{"Token":10}],1]}]}]}},{"body":{"Pub":[42,{"Or":[{"And":[[{"Token":28},{"Rule":16}],null]}]}]}},{"body":{"Pub":[43,{"Or":[{"And":[[{"Token":28},{"Opt":{"Rule":17}}],null]}]}]}},{"body":{"Or":[{"And":[[{"Token":5},{"Token":6},{"Token":28}],null]},{"And":[[{"Token":6},{"Token":28}],null]},{"And":[[{"Token":4}],null]},{"And":[[{"Token":5}],null]}]}},{"body":{"Or":[{"And":[[{"Rule":20}],null]},{"And":[[{"Rule":19}],null]},{"And":[[{"Rule":22}],null]}]}},{"body":{"Pub":[44,{"Or":[{"And":[[{"Token":28}],null]},{"And":[[{"Token":26}],null]}]}]}},{"body":{"Pub":[45,{"Or":[{"And":[[{"Token":13},{"Token":28},{"Rep":{"Rule":18}},{"Token":14}],null]}]}]}},{"body":{"Pub":[46,{"Or":[{"And":[[{"Rep":{"Rule":18}}],null]}]}]}},{"body":{"Pub":[47,{"Or":[{"And":[[{"Token":9},{"Layer":[{"Rule":25},{"Or":[{"And":[[{"Opt":{"Rule":21}},{"Rep":{"Or":[{"And":[[{"Token":3},{"Rule":21}],null]}]}}],null]}]}]},{"Token":10}],null]}]}]}},{"body":{"Or":[]}},{"body":{"Or":[]}},{"body":{"Or":[{"And":[[{"Rep":{"Rule":26}}],null]}]}},{"body":{"Or":[{"And":[[{"Token":9},{"Rule":25},{"Token":10}],1]},{"And":[[{"Not":[10]}],null]}]},"memo":true}]"##;
         let parser: Vec<SynRule> = serde_json::from_str(parser_json).unwrap();
 
-        struct Impl { tokenizer: Vec<LexRule>, parser: Vec<SynRule> };
+        struct Impl { tokenizer: Vec<LexRule>, trivia: Trivia, parser: Vec<SynRule> };
         impl LanguageImpl for Impl {
+            fn name(&self) -> &'static str {
+                "fall"
+            }
+
+            fn extensions(&self) -> &'static [&'static str] {
+                &["fall"]
+            }
+
+            fn version(&self) -> u64 {
+                688162345111675007
+            }
+
             fn parse(&self, text: &str) -> (FileStats, INode) {
-                ::fall_parse::parse(text, &self.tokenizer, &|tokens, stats| {
+                ::fall_parse::parse(text, &self.tokenizer, &self.trivia, &|tokens, stats| {
                     Parser::new(ALL_NODE_TYPES, &self.parser).parse(tokens, stats)
                 })
             }
 
+            fn reparse(&self, file: &File, edit: &Edit, new_text: &str) -> (FileStats, INode) {
+                let parser = Parser::new(ALL_NODE_TYPES, &self.parser);
+                ::fall_parse::reparse(file, edit, new_text, &self.tokenizer, &self.trivia, &parser)
+            }
+
+            fn is_trivia(&self, ty: NodeType) -> bool {
+                self.trivia.contains(ty)
+            }
+
+            fn injections(&self) -> &'static [Injection] {
+                &[
+                    Injection { parent: VERBATIM_DEF, token: HASH_STRING, language: "rust" },
+                ]
+            }
+
+            fn node_types(&self) -> &'static [NodeType] {
+                ALL_NODE_TYPES
+            }
+
             fn node_type_info(&self, ty: NodeType) -> NodeTypeInfo {
                 match ty {
-                    ERROR => NodeTypeInfo { name: "ERROR" },
-                    WHITESPACE => NodeTypeInfo { name: "WHITESPACE" },
-                    EQ => NodeTypeInfo { name: "EQ" },
-                    PIPE => NodeTypeInfo { name: "PIPE" },
-                    STAR => NodeTypeInfo { name: "STAR" },
-                    QUESTION => NodeTypeInfo { name: "QUESTION" },
-                    DOT => NodeTypeInfo { name: "DOT" },
-                    COMMA => NodeTypeInfo { name: "COMMA" },
-                    HASH => NodeTypeInfo { name: "HASH" },
-                    LBRACE => NodeTypeInfo { name: "LBRACE" },
-                    RBRACE => NodeTypeInfo { name: "RBRACE" },
-                    LBRACK => NodeTypeInfo { name: "LBRACK" },
-                    RBRACK => NodeTypeInfo { name: "RBRACK" },
-                    LANGLE => NodeTypeInfo { name: "LANGLE" },
-                    RANGLE => NodeTypeInfo { name: "RANGLE" },
-                    LPAREN => NodeTypeInfo { name: "LPAREN" },
-                    RPAREN => NodeTypeInfo { name: "RPAREN" },
-                    KW_NODE => NodeTypeInfo { name: "KW_NODE" },
-                    KW_CLASS => NodeTypeInfo { name: "KW_CLASS" },
-                    KW_TOKENIZER => NodeTypeInfo { name: "KW_TOKENIZER" },
-                    KW_RULE => NodeTypeInfo { name: "KW_RULE" },
-                    KW_VERBATIM => NodeTypeInfo { name: "KW_VERBATIM" },
-                    KW_AST => NodeTypeInfo { name: "KW_AST" },
-                    KW_PUB => NodeTypeInfo { name: "KW_PUB" },
-                    NUMBER => NodeTypeInfo { name: "NUMBER" },
-                    SIMPLE_STRING => NodeTypeInfo { name: "SIMPLE_STRING" },
-                    HASH_STRING => NodeTypeInfo { name: "HASH_STRING" },
-                    IDENT => NodeTypeInfo { name: "IDENT" },
-                    FALL_FILE => NodeTypeInfo { name: "FALL_FILE" },
-                    TOKENIZER_DEF => NodeTypeInfo { name: "TOKENIZER_DEF" },
-                    LEX_RULE => NodeTypeInfo { name: "LEX_RULE" },
-                    SYN_RULE => NodeTypeInfo { name: "SYN_RULE" },
-                    ATTRIBUTES => NodeTypeInfo { name: "ATTRIBUTES" },
-                    ATTRIBUTE => NodeTypeInfo { name: "ATTRIBUTE" },
-                    STRING => NodeTypeInfo { name: "STRING" },
-                    VERBATIM_DEF => NodeTypeInfo { name: "VERBATIM_DEF" },
-                    AST_DEF => NodeTypeInfo { name: "AST_DEF" },
-                    AST_NODE_DEF => NodeTypeInfo { name: "AST_NODE_DEF" },
-                    AST_CLASS_DEF => NodeTypeInfo { name: "AST_CLASS_DEF" },
-                    METHOD_DEF => NodeTypeInfo { name: "METHOD_DEF" },
-                    AST_SELECTOR => NodeTypeInfo { name: "AST_SELECTOR" },
-                    REF_EXPR => NodeTypeInfo { name: "REF_EXPR" },
-                    CALL_EXPR => NodeTypeInfo { name: "CALL_EXPR" },
-                    SEQ_EXPR => NodeTypeInfo { name: "SEQ_EXPR" },
-                    BLOCK_EXPR => NodeTypeInfo { name: "BLOCK_EXPR" },
+                    ERROR => NodeTypeInfo {
+                        name: "ERROR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    WHITESPACE => NodeTypeInfo {
+                        name: "WHITESPACE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    EQ => NodeTypeInfo {
+                        name: "EQ",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("="),
+                        classes: &[],
+                    },
+                    PIPE => NodeTypeInfo {
+                        name: "PIPE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("|"),
+                        classes: &[],
+                    },
+                    STAR => NodeTypeInfo {
+                        name: "STAR",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("*"),
+                        classes: &[],
+                    },
+                    QUESTION => NodeTypeInfo {
+                        name: "QUESTION",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("?"),
+                        classes: &[],
+                    },
+                    DOT => NodeTypeInfo {
+                        name: "DOT",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("."),
+                        classes: &[],
+                    },
+                    COMMA => NodeTypeInfo {
+                        name: "COMMA",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some(","),
+                        classes: &[],
+                    },
+                    HASH => NodeTypeInfo {
+                        name: "HASH",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("#"),
+                        classes: &[],
+                    },
+                    LBRACE => NodeTypeInfo {
+                        name: "LBRACE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("{"),
+                        classes: &[],
+                    },
+                    RBRACE => NodeTypeInfo {
+                        name: "RBRACE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("}"),
+                        classes: &[],
+                    },
+                    LBRACK => NodeTypeInfo {
+                        name: "LBRACK",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("["),
+                        classes: &[],
+                    },
+                    RBRACK => NodeTypeInfo {
+                        name: "RBRACK",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("]"),
+                        classes: &[],
+                    },
+                    LANGLE => NodeTypeInfo {
+                        name: "LANGLE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("<"),
+                        classes: &[],
+                    },
+                    RANGLE => NodeTypeInfo {
+                        name: "RANGLE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some(">"),
+                        classes: &[],
+                    },
+                    LPAREN => NodeTypeInfo {
+                        name: "LPAREN",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("("),
+                        classes: &[],
+                    },
+                    RPAREN => NodeTypeInfo {
+                        name: "RPAREN",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some(")"),
+                        classes: &[],
+                    },
+                    KW_NODE => NodeTypeInfo {
+                        name: "KW_NODE",
+                        is_token: true,
+                        is_keyword: true,
+                        is_literal: false,
+                        text: Some("node"),
+                        classes: &[],
+                    },
+                    KW_CLASS => NodeTypeInfo {
+                        name: "KW_CLASS",
+                        is_token: true,
+                        is_keyword: true,
+                        is_literal: false,
+                        text: Some("class"),
+                        classes: &[],
+                    },
+                    KW_TOKENIZER => NodeTypeInfo {
+                        name: "KW_TOKENIZER",
+                        is_token: true,
+                        is_keyword: true,
+                        is_literal: false,
+                        text: Some("tokenizer"),
+                        classes: &[],
+                    },
+                    KW_RULE => NodeTypeInfo {
+                        name: "KW_RULE",
+                        is_token: true,
+                        is_keyword: true,
+                        is_literal: false,
+                        text: Some("rule"),
+                        classes: &[],
+                    },
+                    KW_VERBATIM => NodeTypeInfo {
+                        name: "KW_VERBATIM",
+                        is_token: true,
+                        is_keyword: true,
+                        is_literal: false,
+                        text: Some("verbatim"),
+                        classes: &[],
+                    },
+                    KW_AST => NodeTypeInfo {
+                        name: "KW_AST",
+                        is_token: true,
+                        is_keyword: true,
+                        is_literal: false,
+                        text: Some("ast"),
+                        classes: &[],
+                    },
+                    KW_PUB => NodeTypeInfo {
+                        name: "KW_PUB",
+                        is_token: true,
+                        is_keyword: true,
+                        is_literal: false,
+                        text: Some("pub"),
+                        classes: &[],
+                    },
+                    KW_LANGUAGE => NodeTypeInfo {
+                        name: "KW_LANGUAGE",
+                        is_token: true,
+                        is_keyword: true,
+                        is_literal: false,
+                        text: Some("language"),
+                        classes: &[],
+                    },
+                    NUMBER => NodeTypeInfo {
+                        name: "NUMBER",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: true,
+                        text: None,
+                        classes: &[],
+                    },
+                    SIMPLE_STRING => NodeTypeInfo {
+                        name: "SIMPLE_STRING",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: true,
+                        text: None,
+                        classes: &[],
+                    },
+                    HASH_STRING => NodeTypeInfo {
+                        name: "HASH_STRING",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: true,
+                        text: None,
+                        classes: &[],
+                    },
+                    IDENT => NodeTypeInfo {
+                        name: "IDENT",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    FALL_FILE => NodeTypeInfo {
+                        name: "FALL_FILE",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    LANGUAGE_DEF => NodeTypeInfo {
+                        name: "LANGUAGE_DEF",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    TOKENIZER_DEF => NodeTypeInfo {
+                        name: "TOKENIZER_DEF",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    LEX_RULE => NodeTypeInfo {
+                        name: "LEX_RULE",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    SYN_RULE => NodeTypeInfo {
+                        name: "SYN_RULE",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    PARAMETER => NodeTypeInfo {
+                        name: "PARAMETER",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    ATTRIBUTES => NodeTypeInfo {
+                        name: "ATTRIBUTES",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    ATTRIBUTE => NodeTypeInfo {
+                        name: "ATTRIBUTE",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    STRING => NodeTypeInfo {
+                        name: "STRING",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    VERBATIM_DEF => NodeTypeInfo {
+                        name: "VERBATIM_DEF",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    AST_DEF => NodeTypeInfo {
+                        name: "AST_DEF",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    AST_NODE_DEF => NodeTypeInfo {
+                        name: "AST_NODE_DEF",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    AST_CLASS_DEF => NodeTypeInfo {
+                        name: "AST_CLASS_DEF",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    METHOD_DEF => NodeTypeInfo {
+                        name: "METHOD_DEF",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    AST_SELECTOR => NodeTypeInfo {
+                        name: "AST_SELECTOR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    REF_EXPR => NodeTypeInfo {
+                        name: "REF_EXPR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &["expr"],
+                    },
+                    CALL_EXPR => NodeTypeInfo {
+                        name: "CALL_EXPR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &["expr"],
+                    },
+                    SEQ_EXPR => NodeTypeInfo {
+                        name: "SEQ_EXPR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &["expr"],
+                    },
+                    BLOCK_EXPR => NodeTypeInfo {
+                        name: "BLOCK_EXPR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &["expr"],
+                    },
                     _ => panic!("Unknown NodeType: {:?}", ty)
                 }
             }
         }
 
-        Language::new(Impl {
-            tokenizer: vec![
-                LexRule::new(EQ, "=", None),
-                LexRule::new(PIPE, "\\|", None),
-                LexRule::new(STAR, "\\*", None),
-                LexRule::new(QUESTION, "\\?", None),
-                LexRule::new(DOT, "\\.", None),
-                LexRule::new(COMMA, ",", None),
-                LexRule::new(HASH, "\\#", None),
-                LexRule::new(LBRACE, "\\{", None),
-                LexRule::new(RBRACE, "\\}", None),
-                LexRule::new(LBRACK, "\\[", None),
-                LexRule::new(RBRACK, "\\]", None),
-                LexRule::new(LANGLE, "<", None),
-                LexRule::new(RANGLE, ">", None),
-                LexRule::new(LPAREN, "\\(", None),
-                LexRule::new(RPAREN, "\\)", None),
-                LexRule::new(KW_NODE, "node", None),
-                LexRule::new(KW_CLASS, "class", None),
-                LexRule::new(KW_TOKENIZER, "tokenizer", None),
-                LexRule::new(KW_RULE, "rule", None),
-                LexRule::new(KW_VERBATIM, "verbatim", None),
-                LexRule::new(KW_AST, "ast", None),
-                LexRule::new(KW_PUB, "pub", None),
-                LexRule::new(WHITESPACE, "\\s+", None),
-                LexRule::new(NUMBER, "\\d+", None),
+        let tokenizer = vec![
+            LexRule::new(EQ, "=", None),
+            LexRule::new(PIPE, "\\|", None),
+            LexRule::new(STAR, "\\*", None),
+            LexRule::new(QUESTION, "\\?", None),
+            LexRule::new(DOT, "\\.", None),
+            LexRule::new(COMMA, ",", None),
+            LexRule::new(HASH, "\\#", None),
+            LexRule::new(LBRACE, "\\{", None),
+            LexRule::new(RBRACE, "\\}", None),
+            LexRule::new(LBRACK, "\\[", None),
+            LexRule::new(RBRACK, "\\]", None),
+            LexRule::new(LANGLE, "<", None),
+            LexRule::new(RANGLE, ">", None),
+            LexRule::new(LPAREN, "\\(", None),
+            LexRule::new(RPAREN, "\\)", None),
+            LexRule::new(KW_NODE, "node", None),
+            LexRule::new(KW_CLASS, "class", None),
+            LexRule::new(KW_TOKENIZER, "tokenizer", None),
+            LexRule::new(KW_RULE, "rule", None),
+            LexRule::new(KW_VERBATIM, "verbatim", None),
+            LexRule::new(KW_AST, "ast", None),
+            LexRule::new(KW_PUB, "pub", None),
+            LexRule::new(KW_LANGUAGE, "language", None),
+            LexRule::new(WHITESPACE, "\\s+", None),
+            LexRule::new(NUMBER, "\\d+", None),
                 LexRule::new(SIMPLE_STRING, "\'([^\'\\\\]|\\\\.)*\'", None),
-                LexRule::new(HASH_STRING, "r#*", Some(parse_raw_string)),
-                LexRule::new(IDENT, "\\w+", None),
-            ],
+            LexRule::new(HASH_STRING, "r#*", Some(parse_raw_string)),
+            LexRule::new(IDENT, "\\w+", None),
+        ];
+        Language::new(Impl {
+            trivia: Trivia::new(&tokenizer),
+            tokenizer,
             parser: parser,
         })
     };
 }
+
+/// Adds the language to the `registry`.
+/// Use `register(&mut fall_tree::registry::global())` to make it available process-wide.
+pub fn register(registry: &mut Registry) {
+    registry.register(LANG.clone())
+}
+
 fn parse_raw_string(s: &str) -> Option<usize> {
     let quote_start = s.find('"').unwrap();
     let q_hashes = concat!('"', "######", "######", "######", "######", "######");
@@ -173,6 +557,9 @@ impl<'f> AstNode<'f> for FallFile<'f> {
 }
 
 impl<'f> FallFile<'f> {
+    pub fn language_def(&self) -> Option<LanguageDef<'f>> {
+        AstChildren::new(self.node.children()).next()
+    }
     pub fn tokenizer_def(&self) -> Option<TokenizerDef<'f>> {
         AstChildren::new(self.node.children()).next()
     }
@@ -187,6 +574,26 @@ impl<'f> FallFile<'f> {
     }
 }
 #[derive(Clone, Copy)]
+pub struct LanguageDef<'f> { node: Node<'f> }
+
+impl<'f> AstNode<'f> for LanguageDef<'f> {
+    fn ty() -> NodeType { LANGUAGE_DEF }
+    fn new(node: Node<'f>) -> Self {
+        assert_eq!(node.ty(), Self::ty());
+        LanguageDef { node: node }
+    }
+    fn node(&self) -> Node<'f> { self.node }
+}
+
+impl<'f> LanguageDef<'f> {
+    pub fn attributes(&self) -> Option<Attributes<'f>> {
+        AstChildren::new(self.node.children()).next()
+    }
+    pub fn name(&self) -> Text<'f> {
+        child_of_type_exn(self.node, IDENT).text()
+    }
+}
+#[derive(Clone, Copy)]
 pub struct TokenizerDef<'f> { node: Node<'f> }
 
 impl<'f> AstNode<'f> for TokenizerDef<'f> {
@@ -216,6 +623,9 @@ impl<'f> AstNode<'f> for LexRule<'f> {
 }
 
 impl<'f> LexRule<'f> {
+    pub fn attributes(&self) -> Option<Attributes<'f>> {
+        AstChildren::new(self.node.children()).next()
+    }
     pub fn node_type(&self) -> Text<'f> {
         child_of_type_exn(self.node, IDENT).text()
     }
@@ -239,11 +649,31 @@ impl<'f> SynRule<'f> {
     pub fn name(&self) -> Option<Text<'f>> {
         child_of_type(self.node, IDENT).map(|n| n.text())
     }
+    pub fn parameters(&self) -> AstChildren<'f, Parameter<'f>> {
+        AstChildren::new(self.node.children())
+    }
     pub fn body(&self) -> Expr<'f> {
         AstClassChildren::new(self.node.children()).next().unwrap()
     }
 }
 #[derive(Clone, Copy)]
+pub struct Parameter<'f> { node: Node<'f> }
+
+impl<'f> AstNode<'f> for Parameter<'f> {
+    fn ty() -> NodeType { PARAMETER }
+    fn new(node: Node<'f>) -> Self {
+        assert_eq!(node.ty(), Self::ty());
+        Parameter { node: node }
+    }
+    fn node(&self) -> Node<'f> { self.node }
+}
+
+impl<'f> Parameter<'f> {
+    pub fn name(&self) -> Text<'f> {
+        child_of_type_exn(self.node, IDENT).text()
+    }
+}
+#[derive(Clone, Copy)]
 pub struct Attributes<'f> { node: Node<'f> }
 
 impl<'f> AstNode<'f> for Attributes<'f> {
@@ -0,0 +1,106 @@
+extern crate fall_tree;
+extern crate lang_fall;
+
+use fall_tree::{rewrite, TextUnit};
+
+use lang_fall::{LANG_FALL, REF_EXPR, BLOCK_EXPR, SYN_RULE, KW_RULE};
+
+#[test]
+fn ast_classes() {
+    assert_eq!(LANG_FALL.node_type_info(REF_EXPR).classes, &["expr"]);
+    assert_eq!(LANG_FALL.node_type_info(BLOCK_EXPR).classes, &["expr"]);
+    assert!(LANG_FALL.node_type_info(SYN_RULE).classes.is_empty());
+    assert!(LANG_FALL.node_type_info(KW_RULE).is_keyword);
+}
+
+#[test]
+fn format() {
+    let text = "\
+language  foo
+tokenizer { eq '='
+  ident     r\"\\w+\"
+}
+
+
+#[ pratt ]  pub rule  expr { < rep  ident >  }
+rule  pair ( first ,second ) { first second }
+ast { node expr {
+name   IDENT ? .text } class  e { expr }
+node foo { }
+}
+";
+    let file = LANG_FALL.parse(text.to_owned());
+    let edits = lang_fall::format(&file, file.root().range());
+    assert_eq!(rewrite::apply(text, &edits), "\
+language foo
+tokenizer {
+  eq '='
+  ident     r\"\\w+\"
+}
+
+#[pratt]
+pub rule expr { <rep ident>  }
+rule pair(first, second) { first second }
+ast {
+  node expr {
+    name IDENT?.text
+  }
+  class e {
+    expr
+  }
+  node foo {}
+}
+");
+}
+
+#[test]
+fn format_keeps_multiline_bodies() {
+    let text = "\
+pub rule object {
+  '{' <commit>
+    <layer <balanced_body '{' '}'> object_body>
+  '}'
+}
+";
+    let file = LANG_FALL.parse(text.to_owned());
+    assert!(lang_fall::format(&file, file.root().range()).is_empty());
+}
+
+#[test]
+fn indent() {
+    let text = "\
+pub rule foo {
+  bar
+| baz
+}
+ast {
+node a {
+x IDENT.text
+  }
+}
+";
+    let file = LANG_FALL.parse(text.to_owned());
+    let indenter = lang_fall::indenter();
+    let edits = indenter.reindent(&file, file.root().range());
+    assert_eq!(rewrite::apply(text, &edits), "\
+pub rule foo {
+  bar
+  | baz
+}
+ast {
+  node a {
+    x IDENT.text
+  }
+}
+");
+
+    let new_line = |text: &str, offset: usize| {
+        let file = LANG_FALL.parse(text.to_owned());
+        indenter.indent_for_new_line(&file, TextUnit::from_usize(offset))
+    };
+    assert_eq!(new_line("pub rule foo { bar }", 14), "  ");
+    assert_eq!(new_line("pub rule foo { bar }", 19), "");
+    assert_eq!(new_line("ast {\n  node a {\n  }\n}", 16), "    ");
+    assert_eq!(new_line("pub rule foo { bar }", 20), "");
+    assert_eq!(new_line("pub rule foo { 'λ' }", 17), "");
+}
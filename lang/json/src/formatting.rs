@@ -0,0 +1,30 @@
+use fall_tree::{File, TextRange, Edit};
+use fall_tree::format::{Formatter, Rule};
+use fall_tree::indent::Indenter;
+use ::*;
+
+lazy_static! {
+    static ref INDENTER: Indenter = Indenter::new()
+        .indent(OBJECT)
+        .indent(ARRAY);
+
+    static ref FORMATTER: Formatter = Formatter::new()
+        .indenter(INDENTER.clone())
+        .rule(Rule::inside(FIELD).before(COLON).empty())
+        .rule(Rule::inside(FIELD).after(COLON).space())
+        .rule(Rule::inside(OBJECT).after(LBRACE).before(RBRACE).empty())
+        .rule(Rule::inside(ARRAY).after(LBRACK).before(RBRACK).empty())
+        .rule(Rule::anywhere().before(COMMA).empty())
+        .rule(Rule::inside(OBJECT).newline())
+        .rule(Rule::inside(ARRAY).newline());
+}
+
+/// Whitespace edits, which pretty print the part of the `file` within the `range`.
+pub fn format(file: &File, range: TextRange) -> Vec<Edit> {
+    FORMATTER.format(file, range)
+}
+
+/// Indentation of the lines of the `file`.
+pub fn indenter() -> &'static Indenter {
+    &INDENTER
+}
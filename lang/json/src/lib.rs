@@ -7,7 +7,9 @@ extern crate fall_tree;
 extern crate fall_parse;
 
 mod syntax;
+mod formatting;
 
 pub use self::syntax::*;
 pub use self::syntax::LANG as LANG_JSON;
+pub use self::formatting::{format, indenter};
 
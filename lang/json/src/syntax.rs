@@ -1,82 +1,242 @@
 use serde_json;
-use fall_tree::{NodeType, NodeTypeInfo, Language, LanguageImpl, FileStats, INode};
+use fall_tree::{NodeType, NodeTypeInfo, Language, LanguageImpl, Injection, FileStats, INode, File, Edit};
+use fall_tree::registry::Registry;
 pub use fall_tree::{ERROR, WHITESPACE};
 
-pub const LBRACE: NodeType = NodeType(100);
-pub const RBRACE: NodeType = NodeType(101);
-pub const LBRACK: NodeType = NodeType(102);
-pub const RBRACK: NodeType = NodeType(103);
-pub const COLON: NodeType = NodeType(104);
-pub const COMMA: NodeType = NodeType(105);
-pub const NULL: NodeType = NodeType(106);
-pub const BOOL: NodeType = NodeType(107);
-pub const STRING: NodeType = NodeType(108);
-pub const NUMBER: NodeType = NodeType(109);
-pub const FILE: NodeType = NodeType(110);
-pub const OBJECT: NodeType = NodeType(111);
-pub const FIELD: NodeType = NodeType(112);
-pub const ARRAY: NodeType = NodeType(113);
-pub const PRIMITIVE: NodeType = NodeType(114);
+pub const LBRACE: NodeType = NodeType((52100 << 16) | 100);
+pub const RBRACE: NodeType = NodeType((52100 << 16) | 101);
+pub const LBRACK: NodeType = NodeType((52100 << 16) | 102);
+pub const RBRACK: NodeType = NodeType((52100 << 16) | 103);
+pub const COLON: NodeType = NodeType((52100 << 16) | 104);
+pub const COMMA: NodeType = NodeType((52100 << 16) | 105);
+pub const NULL: NodeType = NodeType((52100 << 16) | 106);
+pub const BOOL: NodeType = NodeType((52100 << 16) | 107);
+pub const STRING: NodeType = NodeType((52100 << 16) | 108);
+pub const NUMBER: NodeType = NodeType((52100 << 16) | 109);
+pub const FILE: NodeType = NodeType((52100 << 16) | 110);
+pub const OBJECT: NodeType = NodeType((52100 << 16) | 111);
+pub const FIELD: NodeType = NodeType((52100 << 16) | 112);
+pub const ARRAY: NodeType = NodeType((52100 << 16) | 113);
+pub const PRIMITIVE: NodeType = NodeType((52100 << 16) | 114);
 
 lazy_static! {
     pub static ref LANG: Language = {
-        use fall_parse::{LexRule, SynRule, Parser};
+        use fall_parse::{LexRule, Trivia, SynRule, Parser};
         const ALL_NODE_TYPES: &[NodeType] = &[
             ERROR, WHITESPACE,
             LBRACE, RBRACE, LBRACK, RBRACK, COLON, COMMA, NULL, BOOL, STRING, NUMBER, FILE, OBJECT, FIELD, ARRAY, PRIMITIVE,
         ];
-        let parser_json = r##"[{"body":{"Pub":[12,{"Or":[{"And":[[{"Rule":1}],null]},{"And":[[{"Rule":4}],null]}]}]}},{"body":{"Pub":[13,{"Or":[{"And":[[{"Token":2},{"Layer":[{"Rule":8},{"Rule":2}]},{"Token":3}],1]}]}]}},{"body":{"Or":[{"And":[[{"Rep":{"WithSkip":[{"Token":10},{"Or":[{"And":[[{"Rule":3},{"Or":[{"And":[[{"Token":7},{"NotAhead":"Eof"}],null]},{"And":[["Eof"],null]}]}],1]}]}]}}],null]}]}},{"body":{"Pub":[14,{"Or":[{"And":[[{"Token":10},{"Token":6},{"Rule":6}],1]}]}]}},{"body":{"Pub":[15,{"Or":[{"And":[[{"Token":4},{"Layer":[{"Rule":10},{"Rule":5}]},{"Token":5}],1]}]}]}},{"body":{"Or":[{"And":[[{"Rep":{"WithSkip":[{"Or":[{"And":[[{"Token":8}],null]},{"And":[[{"Token":11}],null]},{"And":[[{"Token":10}],null]},{"And":[[{"Token":9}],null]},{"And":[[{"Token":2}],null]},{"And":[[{"Token":4}],null]}]},{"Or":[{"And":[[{"Rule":6},{"Or":[{"And":[[{"Token":7},{"NotAhead":"Eof"}],null]},{"And":[["Eof"],null]}]}],1]}]}]}}],null]}]}},{"body":{"Or":[{"And":[[{"Rule":7}],null]},{"And":[[{"Rule":1}],null]},{"And":[[{"Rule":4}],null]}]}},{"body":{"Pub":[16,{"Or":[{"And":[[{"Token":8}],null]},{"And":[[{"Token":11}],null]},{"And":[[{"Token":10}],null]},{"And":[[{"Token":9}],null]}]}]}},{"body":{"Or":[{"And":[[{"Rep":{"Rule":9}}],null]}]}},{"body":{"Or":[{"And":[[{"Token":2},{"Rule":8},{"Token":3}],1]},{"And":[[{"Not":[3]}],null]}]}},{"body":{"Or":[{"And":[[{"Rep":{"Rule":11}}],null]}]}},{"body":{"Or":[{"And":[[{"Token":4},{"Rule":10},{"Token":5}],1]},{"And":[[{"Not":[5]}],null]}]}}]"##;
+        let parser_json = r##"[{"body":{"Pub":[12,{"Or":[{"And":[[{"Rule":1}],null]},{"And":[[{"Rule":4}],null]}]}]}},{"body":{"Pub":[13,{"Or":[{"And":[[{"Token":2},{"Layer":[{"Rule":10},{"Rule":2}]},{"Token":3}],1]}]}]}},{"body":{"Or":[{"And":[[{"SepBy":[{"Rule":3},{"Token":7},"Forbid"]}],null]}]}},{"body":{"Pub":[14,{"Or":[{"And":[[{"Token":10},{"Token":6},{"Rule":6}],1]}]}]}},{"body":{"Pub":[15,{"Or":[{"And":[[{"Token":4},{"Layer":[{"Rule":11},{"Rule":5}]},{"Token":5}],1]}]}]}},{"body":{"Or":[{"And":[[{"SepBy":[{"Rule":6},{"Token":7},"Forbid"]}],null]}]}},{"body":{"Or":[{"And":[[{"Rule":7}],null]},{"And":[[{"Rule":1}],null]},{"And":[[{"Rule":4}],null]}]}},{"body":{"Pub":[16,{"Or":[{"And":[[{"Token":8}],null]},{"And":[[{"Token":11}],null]},{"And":[[{"Token":10}],null]},{"And":[[{"Token":9}],null]}]}]}},{"body":{"Or":[]}},{"body":{"Or":[]}},{"body":{"Or":[{"And":[[{"Rep":{"Rule":12}}],null]}]}},{"body":{"Or":[{"And":[[{"Rep":{"Rule":13}}],null]}]}},{"body":{"Or":[{"And":[[{"Token":2},{"Rule":10},{"Token":3}],1]},{"And":[[{"Not":[3]}],null]}]}},{"body":{"Or":[{"And":[[{"Token":4},{"Rule":11},{"Token":5}],1]},{"And":[[{"Not":[5]}],null]}]}}]"##;
         let parser: Vec<SynRule> = serde_json::from_str(parser_json).unwrap();
 
-        struct Impl { tokenizer: Vec<LexRule>, parser: Vec<SynRule> };
+        struct Impl { tokenizer: Vec<LexRule>, trivia: Trivia, parser: Vec<SynRule> };
         impl LanguageImpl for Impl {
+            fn name(&self) -> &'static str {
+                "json"
+            }
+
+            fn extensions(&self) -> &'static [&'static str] {
+                &["json"]
+            }
+
+            fn version(&self) -> u64 {
+                11399789369213419177
+            }
+
             fn parse(&self, text: &str) -> (FileStats, INode) {
-                ::fall_parse::parse(text, &self.tokenizer, &|tokens, stats| {
+                ::fall_parse::parse(text, &self.tokenizer, &self.trivia, &|tokens, stats| {
                     Parser::new(ALL_NODE_TYPES, &self.parser).parse(tokens, stats)
                 })
             }
 
+            fn reparse(&self, file: &File, edit: &Edit, new_text: &str) -> (FileStats, INode) {
+                let parser = Parser::new(ALL_NODE_TYPES, &self.parser);
+                ::fall_parse::reparse(file, edit, new_text, &self.tokenizer, &self.trivia, &parser)
+            }
+
+            fn is_trivia(&self, ty: NodeType) -> bool {
+                self.trivia.contains(ty)
+            }
+
+            fn injections(&self) -> &'static [Injection] {
+                &[
+                    
+                ]
+            }
+
+            fn node_types(&self) -> &'static [NodeType] {
+                ALL_NODE_TYPES
+            }
+
             fn node_type_info(&self, ty: NodeType) -> NodeTypeInfo {
                 match ty {
-                    ERROR => NodeTypeInfo { name: "ERROR" },
-                    WHITESPACE => NodeTypeInfo { name: "WHITESPACE" },
-                    LBRACE => NodeTypeInfo { name: "LBRACE" },
-                    RBRACE => NodeTypeInfo { name: "RBRACE" },
-                    LBRACK => NodeTypeInfo { name: "LBRACK" },
-                    RBRACK => NodeTypeInfo { name: "RBRACK" },
-                    COLON => NodeTypeInfo { name: "COLON" },
-                    COMMA => NodeTypeInfo { name: "COMMA" },
-                    NULL => NodeTypeInfo { name: "NULL" },
-                    BOOL => NodeTypeInfo { name: "BOOL" },
-                    STRING => NodeTypeInfo { name: "STRING" },
-                    NUMBER => NodeTypeInfo { name: "NUMBER" },
-                    FILE => NodeTypeInfo { name: "FILE" },
-                    OBJECT => NodeTypeInfo { name: "OBJECT" },
-                    FIELD => NodeTypeInfo { name: "FIELD" },
-                    ARRAY => NodeTypeInfo { name: "ARRAY" },
-                    PRIMITIVE => NodeTypeInfo { name: "PRIMITIVE" },
+                    ERROR => NodeTypeInfo {
+                        name: "ERROR",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    WHITESPACE => NodeTypeInfo {
+                        name: "WHITESPACE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    LBRACE => NodeTypeInfo {
+                        name: "LBRACE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("{"),
+                        classes: &[],
+                    },
+                    RBRACE => NodeTypeInfo {
+                        name: "RBRACE",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("}"),
+                        classes: &[],
+                    },
+                    LBRACK => NodeTypeInfo {
+                        name: "LBRACK",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("["),
+                        classes: &[],
+                    },
+                    RBRACK => NodeTypeInfo {
+                        name: "RBRACK",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some("]"),
+                        classes: &[],
+                    },
+                    COLON => NodeTypeInfo {
+                        name: "COLON",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some(":"),
+                        classes: &[],
+                    },
+                    COMMA => NodeTypeInfo {
+                        name: "COMMA",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: Some(","),
+                        classes: &[],
+                    },
+                    NULL => NodeTypeInfo {
+                        name: "NULL",
+                        is_token: true,
+                        is_keyword: true,
+                        is_literal: false,
+                        text: Some("null"),
+                        classes: &[],
+                    },
+                    BOOL => NodeTypeInfo {
+                        name: "BOOL",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: true,
+                        text: None,
+                        classes: &[],
+                    },
+                    STRING => NodeTypeInfo {
+                        name: "STRING",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: true,
+                        text: None,
+                        classes: &[],
+                    },
+                    NUMBER => NodeTypeInfo {
+                        name: "NUMBER",
+                        is_token: true,
+                        is_keyword: false,
+                        is_literal: true,
+                        text: None,
+                        classes: &[],
+                    },
+                    FILE => NodeTypeInfo {
+                        name: "FILE",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    OBJECT => NodeTypeInfo {
+                        name: "OBJECT",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    FIELD => NodeTypeInfo {
+                        name: "FIELD",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    ARRAY => NodeTypeInfo {
+                        name: "ARRAY",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
+                    PRIMITIVE => NodeTypeInfo {
+                        name: "PRIMITIVE",
+                        is_token: false,
+                        is_keyword: false,
+                        is_literal: false,
+                        text: None,
+                        classes: &[],
+                    },
                     _ => panic!("Unknown NodeType: {:?}", ty)
                 }
             }
         }
 
+        let tokenizer = vec![
+            LexRule::new(LBRACE, "\\{", None),
+            LexRule::new(RBRACE, "\\}", None),
+            LexRule::new(LBRACK, "\\[", None),
+            LexRule::new(RBRACK, "\\]", None),
+            LexRule::new(COLON, ":", None),
+            LexRule::new(COMMA, ",", None),
+            LexRule::new(NULL, "null", None),
+            LexRule::new(WHITESPACE, "\\s+", None),
+            LexRule::new(BOOL, "true|false", None),
+            LexRule::new(STRING, "\"[^\"]*\"", None),
+            LexRule::new(NUMBER, "\\d+", None),
+        ];
         Language::new(Impl {
-            tokenizer: vec![
-                LexRule::new(LBRACE, "\\{", None),
-                LexRule::new(RBRACE, "\\}", None),
-                LexRule::new(LBRACK, "\\[", None),
-                LexRule::new(RBRACK, "\\]", None),
-                LexRule::new(COLON, ":", None),
-                LexRule::new(COMMA, ",", None),
-                LexRule::new(NULL, "null", None),
-                LexRule::new(WHITESPACE, "\\s+", None),
-                LexRule::new(BOOL, "true|false", None),
-                LexRule::new(STRING, "\"[^\"]*\"", None),
-                LexRule::new(NUMBER, "\\d+", None),
-            ],
+            trivia: Trivia::new(&tokenizer),
+            tokenizer,
             parser: parser,
         })
     };
 }
 
+/// Adds the language to the `registry`.
+/// Use `register(&mut fall_tree::registry::global())` to make it available process-wide.
+pub fn register(registry: &mut Registry) {
+    registry.register(LANG.clone())
+}
+
+
 
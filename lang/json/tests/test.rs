@@ -1,8 +1,12 @@
 extern crate fall_tree;
 extern crate lang_json;
 
-use fall_tree::test_util::check_syntax;
-use lang_json::LANG_JSON;
+use fall_tree::test_util::{check_syntax, check_reparse, check_diff};
+use fall_tree::{dump_file, Edit, TextRange, TextUnit, Preorder, WalkEvent};
+use fall_tree::diff::diff;
+use fall_tree::search::{ancestors, find_leaf_at_offset, child_of_type_exn, children_of_type};
+use fall_tree::rewrite::{self, Rewriter, Fragment};
+use lang_json::{LANG_JSON, ARRAY, OBJECT, FIELD, PRIMITIVE, COMMA, WHITESPACE};
 
 #[test]
 fn obj() {
@@ -189,6 +193,241 @@ fn accidentally_quadratic() {
     assert!(1000 < ticks && ticks < 2000, "spend {} ticks", ticks);
 }
 
+#[test]
+fn reparse_nested_object() {
+    check_reparse(&LANG_JSON, r##"{"a": {"b": 1}, "c": [2]}"##, r##"{"a": {"b": 92}, "c": [2]}"##, r##"
+FILE
+  OBJECT
+    LBRACE "{"
+    FIELD
+      STRING "\"a\""
+      COLON ":"
+      OBJECT
+        LBRACE "{"
+        FIELD
+          STRING "\"b\""
+          COLON ":"
+          PRIMITIVE
+            NUMBER "92"
+        RBRACE "}"
+    COMMA ","
+    FIELD
+      STRING "\"c\""
+      COLON ":"
+      ARRAY
+        LBRACK "["
+        PRIMITIVE
+          NUMBER "2"
+        RBRACK "]"
+    RBRACE "}"
+"##, r##"{"b": 92}"##);
+}
+
+#[test]
+fn reparse_rebalancing_enclosing_layer() {
+    let text = r##"{"a": {"b": [1, 2, {"c": null}]}, "d": 1}"##;
+    let file = LANG_JSON.parse(text.to_owned());
+    let edit = edit(19, 20, "1");
+    let reparsed = file.edit(&edit);
+    let fresh = LANG_JSON.parse(reparsed.text().to_string());
+    assert_eq!(dump_file(&reparsed), dump_file(&fresh));
+}
+
+#[test]
+fn reparse_unclosed_string() {
+    check_reparse(&LANG_JSON, r##"{"a": [1], "b": 2}"##, r##"{"a": [1"], "b": 2}"##, r##"
+FILE
+  OBJECT
+    LBRACE "{"
+    FIELD
+      STRING "\"a\""
+      COLON ":"
+      ARRAY
+        LBRACK "["
+        PRIMITIVE
+          NUMBER "1"
+        ERROR ""
+        PRIMITIVE
+          STRING "\"], \""
+        ERROR ""
+        ERROR
+          ERROR "b"
+          ERROR "\""
+          COLON ":"
+        PRIMITIVE
+          NUMBER "2"
+        ERROR ""
+    RBRACE "}"
+"##, r##"{"a": [1"], "b": 2}"##);
+}
+
+#[test]
+fn diff_changed_value() {
+    check_diff(&LANG_JSON,
+        r##"{"a": 1, "b": [true, null]}"##,
+        r##"{"a": 92, "b": [true, null]}"##,
+        r##"REPLACE NUMBER "1" -> NUMBER "92""##,
+    );
+}
+
+#[test]
+fn diff_added_and_removed_fields() {
+    check_diff(&LANG_JSON,
+        r##"{"a": 1, "b": 2}"##,
+        r##"{"a": 1, "b": 2, "c": {}}"##,
+        r##"
+INSERT COMMA ","
+INSERT WHITESPACE " "
+INSERT FIELD "\"c\": {}"
+"##,
+    );
+    check_diff(&LANG_JSON,
+        r##"{"a": 1, "b": 2}"##,
+        r##"{"b": 2}"##,
+        r##"
+DELETE FIELD "\"a\": 1"
+DELETE COMMA ","
+DELETE WHITESPACE " "
+"##,
+    );
+}
+
+#[test]
+fn diff_maps_unchanged_nodes() {
+    let old = LANG_JSON.parse(r##"{"a": [1, 2], "b": 3}"##.to_owned());
+    let new = LANG_JSON.parse(r##"{"a": [1, 2], "b": 4}"##.to_owned());
+    let diff = diff(&old, &new);
+    let bracket = find_leaf_at_offset(old.root(), TextUnit::from_usize(6)).right_biased().unwrap();
+    let old_array = ancestors(bracket).find(|node| node.ty() == ARRAY).unwrap();
+    let new_array = diff.new_node(old_array).unwrap();
+    assert_eq!(new_array.text().to_string(), "[1, 2]");
+    assert_eq!(new_array.range(), old_array.range());
+    assert_eq!(diff.changes().len(), 1);
+}
+
+#[test]
+fn rewrite_replace_and_insert() {
+    let file = LANG_JSON.parse(r##"{"a": 1, "b": [true]}"##.to_owned());
+    let object = child_of_type_exn(file.root(), OBJECT);
+    let fields: Vec<_> = children_of_type(object, FIELD).collect();
+
+    let mut rewriter = Rewriter::new(&file);
+    rewriter.replace(child_of_type_exn(fields[0], PRIMITIVE), Fragment::parse(&LANG_JSON, "[92]", PRIMITIVE).unwrap());
+    rewriter.insert_after(fields[1], Fragment::token(COMMA, ","));
+    rewriter.insert_after(fields[1], Fragment::token(WHITESPACE, " "));
+    rewriter.insert_after(fields[1], Fragment::parse(&LANG_JSON, r##"{"c": null}"##, FIELD).unwrap());
+    let (new_file, edits) = rewriter.finish();
+
+    assert_eq!(new_file.text().to_string(), r##"{"a": 92, "b": [true], "c": null}"##);
+    assert_eq!(edits, vec![edit(6, 7, "92"), edit(20, 20, r##", "c": null"##)]);
+    assert_eq!(dump_file(&new_file), dump_file(&LANG_JSON.parse(new_file.text().to_string())));
+}
+
+#[test]
+fn rewrite_produces_minimal_edits() {
+    let file = LANG_JSON.parse(r##"{"a": 1, "b": [true]}"##.to_owned());
+    let object = child_of_type_exn(file.root(), OBJECT);
+    let fields: Vec<_> = children_of_type(object, FIELD).collect();
+
+    let mut rewriter = Rewriter::new(&file);
+    rewriter.delete(fields[0]);
+    rewriter.delete(child_of_type_exn(object, COMMA));
+    rewriter.delete(fields[0].parent().unwrap().children().find(|n| n.ty() == WHITESPACE).unwrap());
+    rewriter.replace(fields[1], Fragment::parse(&LANG_JSON, r##"{"b": [true, false]}"##, FIELD).unwrap());
+    let (new_file, edits) = rewriter.finish();
+
+    assert_eq!(new_file.text().to_string(), r##"{"b": [true, false]}"##);
+    assert_eq!(edits, vec![edit(1, 9, ""), edit(19, 19, ", false")]);
+    assert_eq!(dump_file(&new_file), dump_file(&LANG_JSON.parse(new_file.text().to_string())));
+}
+
+fn edit(start: usize, end: usize, insert: &str) -> Edit {
+    Edit {
+        delete: TextRange::from_to(TextUnit::from_usize(start), TextUnit::from_usize(end)),
+        insert: insert.to_owned(),
+    }
+}
+
+#[test]
+fn sibling_and_leaf_navigation() {
+    let file = LANG_JSON.parse(r##"{"a": [1, 2], "b": {}}"##.to_owned());
+    let object = child_of_type_exn(file.root(), OBJECT);
+    let fields: Vec<_> = children_of_type(object, FIELD).collect();
+    let comma = child_of_type_exn(object, COMMA);
+
+    assert_eq!(object.first_child().unwrap().text().to_string(), "{");
+    assert_eq!(object.last_child().unwrap().text().to_string(), "}");
+    assert_eq!(fields[0].next_sibling(), Some(comma));
+    assert_eq!(comma.prev_sibling(), Some(fields[0]));
+    assert_eq!(comma.index_in_parent(), 2);
+    assert_eq!(object.first_child().unwrap().prev_sibling(), None);
+    assert_eq!(file.root().next_sibling(), None);
+
+    let array = child_of_type_exn(fields[0], ARRAY);
+    let rbrack = array.last_child().unwrap();
+    assert_eq!(rbrack.next_leaf(), Some(comma));
+    assert_eq!(comma.prev_leaf(), Some(rbrack));
+    let lbrace = object.first_child().unwrap();
+    assert_eq!(lbrace.prev_leaf(), None);
+    let leaves: Vec<String> = ::std::iter::successors(Some(lbrace), |leaf| leaf.next_leaf())
+        .map(|leaf| leaf.text().to_string())
+        .collect();
+    assert_eq!(leaves.concat(), file.text().to_string());
+}
+
+#[test]
+fn preorder_events_and_skip_subtree() {
+    let file = LANG_JSON.parse(r##"{"a": [1], "b": null}"##.to_owned());
+    let mut events = Vec::new();
+    let mut preorder = Preorder::new(file.root());
+    while let Some(event) = preorder.next() {
+        let (prefix, node) = match event {
+            WalkEvent::Enter(node) => ("> ", node),
+            WalkEvent::Leave(node) => ("< ", node),
+        };
+        if node.ty() == WHITESPACE || node.first_child().is_none() {
+            continue;
+        }
+        events.push(format!("{}{}", prefix, LANG_JSON.node_type_info(node.ty()).name));
+        if event == WalkEvent::Enter(node) && node.ty() == ARRAY {
+            preorder.skip_subtree();
+        }
+    }
+    assert_eq!(events.join(" "), "> FILE > OBJECT > FIELD > ARRAY < ARRAY < FIELD > FIELD > PRIMITIVE < PRIMITIVE < FIELD < OBJECT < FILE");
+}
+
+#[test]
+fn tokens_with_trivia() {
+    let text = "{\n  \"a\": 1,  \"b\": 2\n}\n";
+    let file = LANG_JSON.parse(text.to_owned());
+    let tokens: Vec<_> = file.tokens().collect();
+    assert_eq!(tokens.iter().map(|t| t.text().to_string()).collect::<String>(), text);
+    assert_eq!(tokens.iter().filter(|t| t.is_trivia()).count(), 6);
+
+    let show = |tokens: Vec<::fall_tree::tokens::Token>| -> Vec<String> {
+        tokens.iter().map(|t| t.text().to_string()).collect()
+    };
+    let significant: Vec<_> = file.tokens().significant().collect();
+    let (lbrace, a, comma, b, rbrace) = (significant[0], significant[1], significant[4], significant[5], significant[8]);
+    assert_eq!(lbrace.text().to_string(), "{");
+    assert_eq!(show(lbrace.leading_trivia()), Vec::<String>::new());
+    assert_eq!(show(a.leading_trivia()), vec!["\n  "]);
+    assert_eq!(show(comma.trailing_trivia()), vec!["  "]);
+    assert_eq!(show(b.leading_trivia()), Vec::<String>::new());
+    assert_eq!(show(rbrace.leading_trivia()), vec!["\n"]);
+    assert_eq!(show(rbrace.trailing_trivia()), vec!["\n"]);
+
+    let object = child_of_type_exn(file.root(), OBJECT);
+    let field = child_of_type_exn(object, FIELD);
+    let field_tokens: Vec<_> = field.tokens().map(|t| (t.range(), t.text().to_string())).collect();
+    assert_eq!(field_tokens, vec![
+        (TextRange::from_len(TextUnit::from_usize(4), TextUnit::from_usize(3)), "\"a\"".to_owned()),
+        (TextRange::from_len(TextUnit::from_usize(7), TextUnit::from_usize(1)), ":".to_owned()),
+        (TextRange::from_len(TextUnit::from_usize(8), TextUnit::from_usize(1)), " ".to_owned()),
+        (TextRange::from_len(TextUnit::from_usize(9), TextUnit::from_usize(1)), "1".to_owned()),
+    ]);
+}
+
 #[test]
 fn example() {
     check_syntax(&LANG_JSON, r#"
@@ -355,3 +594,33 @@ FILE
     RBRACE "}"
 "#)
 }
+
+#[test]
+fn format() {
+    let text = r#"{"a" :1,  "b":[1,2 ,[]], "c": {  }, "d":
+  {"e": null}}"#;
+    let file = LANG_JSON.parse(text.to_owned());
+    let edits = lang_json::format(&file, file.root().range());
+    assert_eq!(rewrite::apply(text, &edits), r#"{
+    "a": 1,
+    "b": [
+        1,
+        2,
+        []
+    ],
+    "c": {},
+    "d": {
+        "e": null
+    }
+}"#);
+
+    // Only the second field is formatted, the rest is left as is.
+    let range = TextRange::from_to(TextUnit::from_usize(10), TextUnit::from_usize(23));
+    let edits = lang_json::format(&file, range);
+    assert_eq!(rewrite::apply(text, &edits), r#"{"a" :1,  "b": [
+        1,
+        2,
+        []
+    ], "c": {  }, "d":
+  {"e": null}}"#);
+}
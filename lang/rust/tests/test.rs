@@ -2,7 +2,10 @@ extern crate fall_tree;
 extern crate lang_rust;
 
 use fall_tree::test_util::{check_syntax, check_reparse};
-use lang_rust::LANG_RUST;
+use fall_tree::query::Query;
+use fall_tree::ssr::Ssr;
+use fall_tree::rewrite;
+use lang_rust::{LANG_RUST, FN, LBRACE, NUMBER, IDENT, FN_DEF, ERROR};
 
 
 #[test]
@@ -115,6 +118,33 @@ FILE
 "#);
 }
 
+#[test]
+fn comments() {
+    check_syntax(&LANG_RUST, "// leading\nfn foo() { let a = // value\n 1; // done\n}\n// trailing", r#"
+FILE
+  LINE_COMMENT "// leading"
+  FN_DEF
+    FN "fn"
+    IDENT "foo"
+    LPAREN "("
+    RPAREN ")"
+    BLOCK_EXPR
+      LBRACE "{"
+      STMT
+        KW_LET "let"
+        PATTERN
+          IDENT "a"
+        EQ "="
+        LINE_COMMENT "// value"
+        EXPR
+          NUMBER "1"
+        SEMI ";"
+      LINE_COMMENT "// done"
+      RBRACE "}"
+  LINE_COMMENT "// trailing"
+"#);
+}
+
 #[test]
 fn check_reparse_in_block_body() {
     check_reparse(
@@ -139,5 +169,169 @@ FILE
           NUMBER "1"
         SEMI ";"
       RBRACE "}""#,
-        "fn foo() { let a = 1; }")
+        "{ let a = 1; }")
+}
+
+#[test]
+fn check_reparse_changed_block_borders() {
+    check_reparse(
+        &LANG_RUST,
+        "fn foo() { let a = 1; } fn bar() {}",
+        "fn foo() { let a = 1; }} fn bar() {}",
+        r#"
+FILE
+  FN_DEF
+    FN "fn"
+    IDENT "foo"
+    LPAREN "("
+    RPAREN ")"
+    BLOCK_EXPR
+      LBRACE "{"
+      STMT
+        KW_LET "let"
+        PATTERN
+          IDENT "a"
+        EQ "="
+        EXPR
+          NUMBER "1"
+        SEMI ";"
+      RBRACE "}"
+  ERROR
+    RBRACE "}"
+  FN_DEF
+    FN "fn"
+    IDENT "bar"
+    LPAREN "("
+    RPAREN ")"
+    BLOCK_EXPR
+      LBRACE "{"
+      RBRACE "}""#,
+        "fn foo() { let a = 1; }} fn bar() {}")
+}
+
+#[test]
+fn node_type_info() {
+    let fn_info = LANG_RUST.node_type_info(FN);
+    assert!(fn_info.is_token && fn_info.is_keyword && !fn_info.is_literal);
+    assert_eq!(fn_info.display_name(), "'fn'");
+
+    let lbrace = LANG_RUST.node_type_info(LBRACE);
+    assert!(lbrace.is_token && !lbrace.is_keyword);
+    assert_eq!(lbrace.text, Some("{"));
+    assert_eq!(lbrace.display_name(), "'{'");
+
+    let number = LANG_RUST.node_type_info(NUMBER);
+    assert!(number.is_token && number.is_literal && !number.is_keyword);
+    assert_eq!(number.display_name(), "NUMBER");
+    assert!(!LANG_RUST.node_type_info(IDENT).is_literal);
+
+    let fn_def = LANG_RUST.node_type_info(FN_DEF);
+    assert!(!fn_def.is_token);
+    assert_eq!(fn_def.display_name(), "FN_DEF");
+    assert!(!LANG_RUST.node_type_info(ERROR).is_token);
+}
+
+#[test]
+fn node_types() {
+    assert!(LANG_RUST.node_types().contains(&FN_DEF));
+    assert!(LANG_RUST.node_types().contains(&ERROR));
+    assert_eq!(LANG_RUST.node_type_by_name("FN_DEF"), Some(FN_DEF));
+    assert_eq!(LANG_RUST.node_type_by_name("LBRACE"), Some(LBRACE));
+    assert_eq!(LANG_RUST.node_type_by_name("NO_SUCH_TYPE"), None);
+}
+
+#[test]
+fn errors() {
+    let check = |text: &str, expected: &[(u32, u32, &str)]| {
+        let file = LANG_RUST.parse(text.to_owned());
+        let actual: Vec<(u32, u32, String)> = file.errors().into_iter()
+            .map(|(range, message)| (range.start().as_u32(), range.end().as_u32(), message))
+            .collect();
+        let expected: Vec<(u32, u32, String)> = expected.iter()
+            .map(|&(start, end, message)| (start, end, message.to_owned()))
+            .collect();
+        assert_eq!(actual, expected, "{}", text);
+    };
+    check("fn foo() {}", &[]);
+    check("fn foo", &[(6, 6, "'(' expected")]);
+    check("fn foo() { let a = 1 }", &[(20, 20, "';' expected")]);
+    check("fn foo() { let = 1; }", &[(14, 14, "PATTERN expected"), (15, 19, "unexpected '='")]);
+    check("fn foo() {}} fn bar() {}", &[(11, 12, "unexpected '}', expected 'pub', 'fn' or 'struct'")]);
+    check("fn $() {}", &[(2, 2, "IDENT expected"), (3, 9, "unexpected \"$\"")]);
+}
+
+#[test]
+fn query() {
+    let file = LANG_RUST.parse("\
+fn foo() { let a = 1; let b = 2; }
+struct S {}
+fn bar() {}
+".to_owned());
+    let query = Query::new("
+        ; functions with their statements
+        (FN_DEF (IDENT) @name (BLOCK_EXPR (STMT)* @stmt))
+        (STRUCT_DEF (IDENT) @name)
+    ").unwrap();
+    let matches = query.matches(file.root());
+    let actual: Vec<String> = matches.iter().map(|m| {
+        let captures: Vec<String> = m.captures.iter()
+            .map(|&(ref name, node)| format!("@{} {:?}", name, node.text().to_string()))
+            .collect();
+        format!("{} {}", m.pattern, captures.join(" "))
+    }).collect();
+    assert_eq!(actual, vec![
+        "0 @name \"foo\" @stmt \"let a = 1;\" @stmt \"let b = 2;\"",
+        "1 @name \"S\"",
+        "0 @name \"bar\"",
+    ]);
+    assert_eq!(matches[1].get("name").unwrap().text(), "S");
+
+    let query = Query::new("(FN_DEF (_) (LPAREN) (STMT)+)").unwrap();
+    assert!(query.matches(file.root()).is_empty());
+    let query = Query::new("(BLOCK_EXPR (STMT) (STMT) (STMT)?)").unwrap();
+    assert_eq!(query.matches(file.root()).len(), 1);
+
+    let error = Query::new("(FN_DEF (IDENT) @)").unwrap_err();
+    assert_eq!((error.offset, error.message.as_str()), (17, "expected a capture name"));
+}
+
+#[test]
+fn query_with_failing_tail_is_fast() {
+    let text = format!("fn foo() {{ {} }}", "let a = 1; ".repeat(300));
+    let file = LANG_RUST.parse(text);
+    let query = Query::new("(BLOCK_EXPR (STMT)* (STMT)+ (MISSING))").unwrap();
+    assert!(query.matches(file.root()).is_empty());
+    let query = Query::new("(BLOCK_EXPR (STMT)* @stmt (RBRACE))").unwrap();
+    assert_eq!(query.matches(file.root())[0].captures.len(), 300);
+}
+
+#[test]
+fn ssr() {
+    let text = "\
+fn foo() { let a = 1; let  b = // two
+    2 ; }
+fn bar() {}
+";
+    let file = LANG_RUST.parse(text.to_owned());
+    let ssr = Ssr::new(&LANG_RUST, "let $p = $e;", "let $p: i32 = $e;").unwrap();
+    let matches = ssr.matches(&file);
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].placeholders["e"].text(), "1");
+    assert_eq!(rewrite::apply(text, &ssr.edits(&file)), "\
+fn foo() { let a: i32 = 1; let  b = // two
+    2 ; }
+fn bar() {}
+");
+
+    let ssr = Ssr::new(&LANG_RUST, "fn $name() {}", "pub fn $name() {}").unwrap();
+    assert_eq!(rewrite::apply(text, &ssr.edits(&file)), text.replace("fn bar", "pub fn bar"));
+
+    let ssr = Ssr::new(&LANG_RUST, "struct $s {} struct $s {}", "struct $s {}").unwrap();
+    let file = LANG_RUST.parse("struct A {} struct  A {}".to_owned());
+    assert_eq!(rewrite::apply(&file.text().to_string(), &ssr.edits(&file)), "struct A {}");
+    let file = LANG_RUST.parse("struct A {} struct B {}".to_owned());
+    assert!(ssr.edits(&file).is_empty());
+
+    let error = Ssr::new(&LANG_RUST, "let $p = 1;", "let $q = 1;").err().unwrap();
+    assert_eq!(error.message, "unknown placeholder $q");
 }